@@ -0,0 +1,46 @@
+use serenity::builder::{CreateCommand, CreateInteractionResponse, CreateInteractionResponseMessage};
+use serenity::model::application::{CommandInteraction, ResolvedOption, ResolvedValue};
+use serenity::prelude::*;
+
+use crate::{link_archive, linkfix_settings, mirror_health, reaction_stats, roster_commands, speller, trigger_commands};
+
+/// Every slash command the bot registers on startup.
+pub fn all() -> Vec<CreateCommand> {
+    vec![
+        mirror_health::register(),
+        linkfix_settings::register(),
+        link_archive::register(),
+        trigger_commands::register(),
+        speller::register(),
+        reaction_stats::register(),
+        roster_commands::register_roster(),
+        roster_commands::register_player(),
+    ]
+}
+
+/// Reply to a slash command with a message only the caller can see.
+pub async fn reply_ephemeral(
+    ctx: &Context,
+    command: &CommandInteraction,
+    content: impl Into<String>,
+) -> serenity::Result<()> {
+    let response = CreateInteractionResponseMessage::new()
+        .content(content)
+        .ephemeral(true);
+    command
+        .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+        .await
+}
+
+/// The first subcommand and its options, e.g. `disable` + `[channel]` for `/linkfix disable`.
+pub fn subcommand<'a>(options: &'a [ResolvedOption<'a>]) -> Option<(&'a str, &'a [ResolvedOption<'a>])> {
+    options.iter().find_map(|option| match &option.value {
+        ResolvedValue::SubCommand(inner) => Some((option.name, inner.as_slice())),
+        _ => None,
+    })
+}
+
+/// Look up a named option value.
+pub fn option<'a>(options: &'a [ResolvedOption<'a>], name: &str) -> Option<&'a ResolvedValue<'a>> {
+    options.iter().find(|option| option.name == name).map(|option| &option.value)
+}
@@ -0,0 +1,162 @@
+use serenity::model::id::{ChannelId, UserId};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Once this many buckets are tracked, full ones are dropped to bound memory.
+const PRUNE_THRESHOLD: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scope {
+    Trigger,
+    Channel,
+    User,
+}
+
+impl Scope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Scope::Trigger => "trigger",
+            Scope::Channel => "channel",
+            Scope::User => "user",
+        }
+    }
+}
+
+/// Bucket size and refill rate for one scope. A zero burst turns the limit off.
+#[derive(Debug, Clone, Copy)]
+pub struct BucketConfig {
+    pub burst: f64,
+    pub per_minute: f64,
+}
+
+impl BucketConfig {
+    /// Read `{prefix}_BURST` and `{prefix}_PER_MINUTE`, falling back to the defaults.
+    fn from_env(prefix: &str, burst: f64, per_minute: f64) -> Self {
+        let read = |suffix: &str, default: f64| {
+            std::env::var(format!("{}_{}", prefix, suffix))
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+        BucketConfig {
+            burst: read("BURST", burst),
+            per_minute: read("PER_MINUTE", per_minute),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, config: BucketConfig, now: Instant) {
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * config.per_minute / 60.0).min(config.burst);
+        self.updated = now;
+    }
+}
+
+#[derive(Debug, Default)]
+struct Buckets {
+    buckets: HashMap<(Scope, String), Bucket>,
+}
+
+/// Token buckets per trigger, per channel and per user, shared by every reaction the bot
+/// adds.
+#[derive(Debug, Clone)]
+pub struct Cooldowns {
+    trigger: BucketConfig,
+    channel: BucketConfig,
+    user: BucketConfig,
+    state: Arc<Mutex<Buckets>>,
+}
+
+impl Cooldowns {
+    pub fn new(trigger: BucketConfig, channel: BucketConfig, user: BucketConfig) -> Self {
+        Cooldowns {
+            trigger,
+            channel,
+            user,
+            state: Arc::default(),
+        }
+    }
+
+    /// Limits from REACTION_LIMIT_{TRIGGER,CHANNEL,USER}_{BURST,PER_MINUTE}.
+    pub fn from_env() -> Self {
+        Self::new(
+            BucketConfig::from_env("REACTION_LIMIT_TRIGGER", 5.0, 4.0),
+            BucketConfig::from_env("REACTION_LIMIT_CHANNEL", 8.0, 6.0),
+            BucketConfig::from_env("REACTION_LIMIT_USER", 3.0, 2.0),
+        )
+    }
+
+    fn config(&self, scope: Scope) -> BucketConfig {
+        match scope {
+            Scope::Trigger => self.trigger,
+            Scope::Channel => self.channel,
+            Scope::User => self.user,
+        }
+    }
+
+    /// Take a token from the trigger's, channel's and user's buckets, or from none of them
+    /// if any is empty. Returns the scope that ran dry.
+    pub fn acquire(&self, trigger: &str, channel_id: ChannelId, user_id: UserId) -> Result<(), Scope> {
+        let keys = [
+            (Scope::Trigger, trigger.to_string()),
+            (Scope::Channel, channel_id.to_string()),
+            (Scope::User, user_id.to_string()),
+        ];
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+
+        for (scope, key) in &keys {
+            let config = self.config(*scope);
+            if config.burst <= 0.0 {
+                continue;
+            }
+            let bucket = state.buckets.entry((*scope, key.clone())).or_insert(Bucket {
+                tokens: config.burst,
+                updated: now,
+            });
+            bucket.refill(config, now);
+            if bucket.tokens < 1.0 {
+                return Err(*scope);
+            }
+        }
+        for key in &keys {
+            if let Some(bucket) = state.buckets.get_mut(key) {
+                bucket.tokens -= 1.0;
+            }
+        }
+
+        if state.buckets.len() > PRUNE_THRESHOLD {
+            state.buckets.retain(|(scope, _), bucket| {
+                let config = self.config(*scope);
+                bucket.refill(config, now);
+                bucket.tokens < config.burst
+            });
+        }
+        Ok(())
+    }
+
+    /// `acquire`, logging when the reaction is skipped.
+    pub fn allow(&self, trigger: &str, channel_id: ChannelId, user_id: UserId) -> bool {
+        match self.acquire(trigger, channel_id, user_id) {
+            Ok(()) => true,
+            Err(scope) => {
+                println!(
+                    "[cooldown] Skipping '{}' in channel {} for user {}: {} limit reached",
+                    trigger,
+                    channel_id,
+                    user_id,
+                    scope.as_str()
+                );
+                false
+            }
+        }
+    }
+}
@@ -0,0 +1,260 @@
+/// A link found in message text, outside code and suppressed contexts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoundLink<'a> {
+    pub url: &'a str,
+    /// The link sat inside `||spoiler||` markers; reposts should keep it hidden.
+    pub spoiler: bool,
+}
+
+impl FoundLink<'_> {
+    /// Wrap `converted` the same way the original link was wrapped.
+    pub fn rewrap(&self, converted: &str) -> String {
+        if self.spoiler {
+            format!("||{}||", converted)
+        } else {
+            converted.to_string()
+        }
+    }
+}
+
+fn starts_with_ignore_case(haystack: &str, needle: &str) -> bool {
+    haystack.len() >= needle.len()
+        && haystack.as_bytes()[..needle.len()].eq_ignore_ascii_case(needle.as_bytes())
+}
+
+fn is_url_start(rest: &str) -> bool {
+    starts_with_ignore_case(rest, "https://") || starts_with_ignore_case(rest, "http://")
+}
+
+fn char_len_at(text: &str, i: usize) -> usize {
+    text[i..].chars().next().map_or(1, char::len_utf8)
+}
+
+/// Length of the URL at the start of `rest`, following Discord's rules for where a bare link
+/// ends: whitespace, angle brackets or a spoiler marker, minus trailing punctuation and
+/// closing brackets that were never opened inside the URL.
+fn url_len(rest: &str) -> usize {
+    let mut end = rest
+        .char_indices()
+        .find(|&(i, c)| c.is_whitespace() || c == '<' || c == '>' || rest[i..].starts_with("||"))
+        .map_or(rest.len(), |(i, _)| i);
+
+    loop {
+        let candidate = &rest[..end];
+        let Some(last) = candidate.chars().last() else {
+            break;
+        };
+        let trim = match last {
+            '.' | ',' | ':' | ';' | '!' | '?' | '\'' | '"' | '*' | '_' | '~' => true,
+            ')' => candidate.matches('(').count() < candidate.matches(')').count(),
+            ']' => candidate.matches('[').count() < candidate.matches(']').count(),
+            _ => false,
+        };
+        if !trim {
+            break;
+        }
+        end -= last.len_utf8();
+    }
+    end
+}
+
+/// Find the `](url)` tail of a masked link starting at the `[` at the front of `rest`.
+/// Returns the byte length of the whole `[text](url)` and the URL, unless the URL is
+/// suppressed with `<...>`.
+fn masked_link(rest: &str) -> Option<(usize, Option<&str>)> {
+    let close = rest.find(']')?;
+    // Masked link text can't span lines
+    if !rest[close..].starts_with("](") || rest[..close].contains('\n') {
+        return None;
+    }
+    let target_start = close + 2;
+    // The target ends at the `)` that balances `](`, so `/wiki/Foo_(bar)` stays whole
+    let mut depth = 0usize;
+    let target_len = rest[target_start..].char_indices().find_map(|(i, c)| match c {
+        '(' => {
+            depth += 1;
+            None
+        }
+        ')' if depth == 0 => Some(i),
+        ')' => {
+            depth -= 1;
+            None
+        }
+        _ => None,
+    })?;
+    let target = &rest[target_start..target_start + target_len];
+    let whole = target_start + target_len + 1;
+
+    if target.starts_with('<') && target.ends_with('>') {
+        return Some((whole, None));
+    }
+    if is_url_start(target) && !target.contains(char::is_whitespace) {
+        return Some((whole, Some(target)));
+    }
+    None
+}
+
+fn backtick_run(text: &str) -> usize {
+    text.bytes().take_while(|&b| b == b'`').count()
+}
+
+/// Where the next run of exactly `len` backticks starts in `text`.
+fn find_backtick_run(text: &str, len: usize) -> Option<usize> {
+    let mut i = 0;
+    while let Some(start) = text[i..].find('`') {
+        let at = i + start;
+        let run = backtick_run(&text[at..]);
+        if run == len {
+            return Some(at);
+        }
+        i = at + run;
+    }
+    None
+}
+
+/// Every link Discord would show as live in `text`. Links in inline code, code blocks and
+/// `<suppressed>` form are skipped; links inside spoilers are flagged so the repost can
+/// keep them hidden.
+pub fn find_links(text: &str) -> Vec<FoundLink<'_>> {
+    let mut links = Vec::new();
+    let mut spoiler = false;
+    let mut i = 0;
+
+    while i < text.len() {
+        let rest = &text[i..];
+
+        if rest.starts_with('\\') {
+            // Escaped markdown character: skip the backslash and what it escapes
+            i += 1;
+            if i < text.len() {
+                i += char_len_at(text, i);
+            }
+            continue;
+        }
+
+        if let Some(body) = rest.strip_prefix("```") {
+            match body.find("```") {
+                Some(close) => {
+                    i += 3 + close + 3;
+                    continue;
+                }
+                None => {
+                    i += 3;
+                    continue;
+                }
+            }
+        }
+
+        if rest.starts_with('`') {
+            // Inline code closes on a run of the same number of backticks; an unclosed run
+            // is shown literally
+            let run = backtick_run(rest);
+            i += run;
+            if let Some(close) = find_backtick_run(&rest[run..], run) {
+                i += close + run;
+            }
+            continue;
+        }
+
+        // An opening `||` with no closing partner is shown literally
+        if rest.starts_with("||") && (spoiler || rest[2..].contains("||")) {
+            spoiler = !spoiler;
+            i += 2;
+            continue;
+        }
+
+        if rest.starts_with('<') && is_url_start(&rest[1..]) {
+            let body = &rest[1..];
+            if let Some(close) = body.find(|c: char| c == '>' || c.is_whitespace()) {
+                if body[close..].starts_with('>') {
+                    i += 1 + close + 1;
+                    continue;
+                }
+            }
+        }
+
+        if rest.starts_with('[') {
+            if let Some((len, url)) = masked_link(rest) {
+                if let Some(url) = url {
+                    links.push(FoundLink { url, spoiler });
+                }
+                i += len;
+                continue;
+            }
+        }
+
+        if is_url_start(rest) {
+            let len = url_len(rest);
+            links.push(FoundLink {
+                url: &rest[..len],
+                spoiler,
+            });
+            i += len.max(1);
+            continue;
+        }
+
+        i += char_len_at(text, i);
+    }
+
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn links(text: &str) -> Vec<(&str, bool)> {
+        find_links(text).into_iter().map(|link| (link.url, link.spoiler)).collect()
+    }
+
+    #[test]
+    fn find_links_table() {
+        let cases: &[(&str, &[(&str, bool)])] = &[
+            // Bare links
+            ("see https://x.com/a", &[("https://x.com/a", false)]),
+            ("HTTP://X.COM/a and http://b.com", &[("HTTP://X.COM/a", false), ("http://b.com", false)]),
+            ("no links here", &[]),
+            // Inline code and fenced blocks
+            ("`https://x.com/a` https://x.com/b", &[("https://x.com/b", false)]),
+            ("```\nhttps://x.com/a\n``` after", &[]),
+            ("```rust\nlet u = \"https://x.com/a\";\n```\nhttps://x.com/b", &[("https://x.com/b", false)]),
+            ("unclosed ` https://x.com/a", &[("https://x.com/a", false)]),
+            ("``a https://x.com/a b``", &[]),
+            ("``https://x.com/b``", &[]),
+            ("``a ` https://x.com/a`` https://x.com/b", &[("https://x.com/b", false)]),
+            // Suppressed links
+            ("<https://x.com/a> https://x.com/b", &[("https://x.com/b", false)]),
+            ("<https://x.com/a", &[("https://x.com/a", false)]),
+            // Spoilers
+            ("||https://x.com/a|| https://x.com/b", &[("https://x.com/a", true), ("https://x.com/b", false)]),
+            ("|| https://x.com/a ||", &[("https://x.com/a", true)]),
+            ("|| not closed https://x.com/a", &[("https://x.com/a", false)]),
+            // Trailing punctuation and brackets
+            ("look: https://x.com/a.", &[("https://x.com/a", false)]),
+            ("(https://x.com/a)", &[("https://x.com/a", false)]),
+            ("https://en.wikipedia.org/wiki/Foo_(bar)!", &[("https://en.wikipedia.org/wiki/Foo_(bar)", false)]),
+            ("\"https://x.com/a\",", &[("https://x.com/a", false)]),
+            ("**https://x.com/a**", &[("https://x.com/a", false)]),
+            // Masked links
+            ("[here](https://x.com/a) done", &[("https://x.com/a", false)]),
+            ("[x](https://en.wikipedia.org/wiki/Foo_(bar))", &[("https://en.wikipedia.org/wiki/Foo_(bar)", false)]),
+            ("[x](<https://x.com/a>)", &[]),
+            ("||[x](https://x.com/a)||", &[("https://x.com/a", true)]),
+            ("[not a link](nope) https://x.com/b", &[("https://x.com/b", false)]),
+            // Escapes
+            ("\\[x](https://x.com/a)", &[("https://x.com/a", false)]),
+            ("\\`https://x.com/a \\`", &[("https://x.com/a", false)]),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(links(text), expected.to_vec(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn rewrap_keeps_spoilers() {
+        let found = find_links("||https://x.com/a||");
+        assert_eq!(found[0].rewrap("https://xcancel.com/a"), "||https://xcancel.com/a||");
+        let found = find_links("https://x.com/a");
+        assert_eq!(found[0].rewrap("https://xcancel.com/a"), "https://xcancel.com/a");
+    }
+}
@@ -0,0 +1,93 @@
+use chrono::{DateTime, Duration, Utc};
+use serenity::model::id::{ChannelId, GuildId, MessageId};
+use sqlx::PgPool;
+
+/// Reaction left on a message whose link was already shared.
+pub const MARKER_EMOJI: char = '🔁';
+
+/// What to do when someone posts a link the guild has already seen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateMode {
+    Off,
+    Reply,
+    React,
+}
+
+impl DuplicateMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "off" => Some(DuplicateMode::Off),
+            "reply" => Some(DuplicateMode::Reply),
+            "react" => Some(DuplicateMode::React),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DuplicateMode::Off => "off",
+            DuplicateMode::Reply => "reply",
+            DuplicateMode::React => "react",
+        }
+    }
+}
+
+/// The earliest posting of a link within the guild's window.
+#[derive(Debug)]
+pub struct FirstPosting {
+    pub channel_id: ChannelId,
+    pub message_id: MessageId,
+    pub author_name: String,
+    pub posted_at: DateTime<Utc>,
+}
+
+impl FirstPosting {
+    pub fn jump_url(&self, guild_id: GuildId) -> String {
+        format!(
+            "https://discord.com/channels/{}/{}/{}",
+            guild_id, self.channel_id, self.message_id
+        )
+    }
+}
+
+/// Look for an earlier message in the guild that shared the same canonical link within
+/// `window_hours`.
+pub async fn find_first_posting(
+    pool: &PgPool,
+    guild_id: GuildId,
+    canonical_url: &str,
+    window_hours: i32,
+    exclude_message: MessageId,
+) -> anyhow::Result<Option<FirstPosting>> {
+    let since = Utc::now() - Duration::hours(window_hours.into());
+    let row = sqlx::query_as::<_, (i64, i64, String, DateTime<Utc>)>(
+        "SELECT channel_id, message_id, author_name, posted_at
+         FROM shared_links
+         WHERE guild_id = $1 AND canonical_url = $2 AND posted_at >= $3 AND message_id <> $4
+         ORDER BY posted_at
+         LIMIT 1",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(canonical_url)
+    .bind(since)
+    .bind(exclude_message.get() as i64)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|(channel_id, message_id, author_name, posted_at)| FirstPosting {
+        channel_id: ChannelId::new(channel_id as u64),
+        message_id: MessageId::new(message_id as u64),
+        author_name,
+        posted_at,
+    }))
+}
+
+pub fn format_reply(first: &FirstPosting, guild_id: GuildId) -> String {
+    format!(
+        "{} Already posted by **{}** <t:{}:R>: {}",
+        MARKER_EMOJI,
+        first.author_name,
+        first.posted_at.timestamp(),
+        first.jump_url(guild_id)
+    )
+}
@@ -0,0 +1,158 @@
+use serenity::model::channel::ReactionType;
+use serenity::model::guild::Emoji;
+use serenity::model::id::{EmojiId, GuildId};
+use serenity::prelude::*;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use crate::triggers::EmojiSpec;
+
+/// A custom emoji the bot uses by name: each name is tried in order in the message's guild,
+/// then the unicode fallback, if any.
+#[derive(Debug, Clone, Copy)]
+pub struct EmojiRef {
+    pub names: &'static [&'static str],
+    pub fallback: Option<&'static str>,
+}
+
+impl EmojiRef {
+    /// Whether `reaction` is any emoji in this chain, by name, so re-uploads still count.
+    pub fn matches(&self, reaction: &ReactionType) -> bool {
+        match reaction {
+            ReactionType::Custom { name: Some(name), .. } => self.names.iter().any(|n| n.eq_ignore_ascii_case(name)),
+            ReactionType::Unicode(emoji) => self.fallback == Some(emoji.as_str()),
+            _ => false,
+        }
+    }
+
+    pub fn spec(&self) -> EmojiSpec {
+        EmojiSpec::Named {
+            names: self.names.iter().map(|name| name.to_string()).collect(),
+            fallback: self.fallback.map(str::to_string),
+        }
+    }
+}
+
+pub const AC: EmojiRef = EmojiRef {
+    names: &["AC", "AC_ALT"],
+    fallback: None,
+};
+pub const TI: EmojiRef = EmojiRef {
+    names: &["TI"],
+    fallback: None,
+};
+pub const VA: EmojiRef = EmojiRef {
+    names: &["VA"],
+    fallback: None,
+};
+pub const TE: EmojiRef = EmojiRef {
+    names: &["TE"],
+    fallback: None,
+};
+pub const D: EmojiRef = EmojiRef {
+    names: &["D_", "D"],
+    fallback: None,
+};
+pub const MADI_KNIFE: EmojiRef = EmojiRef {
+    names: &["madi_knife", "knife"],
+    fallback: Some("🔪"),
+};
+
+#[derive(Debug, Clone)]
+struct GuildEmoji {
+    id: EmojiId,
+    name: String,
+    animated: bool,
+}
+
+impl GuildEmoji {
+    fn reaction(&self) -> ReactionType {
+        ReactionType::Custom {
+            animated: self.animated,
+            id: self.id,
+            name: Some(self.name.clone()),
+        }
+    }
+}
+
+/// An exact name match wins over a case-insensitive one.
+fn named<'a>(emojis: &'a [GuildEmoji], name: &str) -> Option<&'a GuildEmoji> {
+    emojis
+        .iter()
+        .find(|emoji| emoji.name == name)
+        .or_else(|| emojis.iter().find(|emoji| emoji.name.eq_ignore_ascii_case(name)))
+}
+
+/// Every guild's custom emoji, kept current from guild create and emoji update events.
+#[derive(Debug, Clone, Default)]
+pub struct EmojiRegistry {
+    guilds: Arc<RwLock<HashMap<GuildId, Vec<GuildEmoji>>>>,
+}
+
+impl EmojiRegistry {
+    /// Replace what's known about a guild's emoji.
+    pub fn load_guild<'a>(&self, guild_id: GuildId, emojis: impl IntoIterator<Item = &'a Emoji>) {
+        let emojis: Vec<GuildEmoji> = emojis
+            .into_iter()
+            .filter(|emoji| emoji.available)
+            .map(|emoji| GuildEmoji {
+                id: emoji.id,
+                name: emoji.name.clone(),
+                animated: emoji.animated,
+            })
+            .collect();
+        println!("[emoji_registry] Loaded {} emoji for guild {}", emojis.len(), guild_id);
+        self.guilds.write().unwrap().insert(guild_id, emojis);
+    }
+
+    /// Load every guild already in the cache.
+    pub fn load_from_cache(&self, ctx: &Context) {
+        for guild_id in ctx.cache.guilds() {
+            let emojis: Vec<Emoji> = match ctx.cache.guild(guild_id) {
+                Some(guild) => guild.emojis.values().cloned().collect(),
+                None => continue,
+            };
+            self.load_guild(guild_id, &emojis);
+        }
+    }
+
+    fn by_name(&self, guild_id: GuildId, name: &str) -> Option<ReactionType> {
+        let guilds = self.guilds.read().unwrap();
+        named(guilds.get(&guild_id)?, name).map(GuildEmoji::reaction)
+    }
+
+    fn by_names(&self, guild_id: Option<GuildId>, names: &[&str], fallback: Option<&str>) -> Option<ReactionType> {
+        guild_id
+            .and_then(|guild_id| names.iter().find_map(|name| self.by_name(guild_id, name)))
+            .or_else(|| fallback.map(|emoji| ReactionType::Unicode(emoji.to_string())))
+    }
+
+    pub fn resolve_ref(&self, guild_id: Option<GuildId>, emoji: &EmojiRef) -> Option<ReactionType> {
+        self.by_names(guild_id, emoji.names, emoji.fallback)
+    }
+
+    /// Turn a stored emoji into something the bot can react with in this guild. A custom
+    /// emoji that isn't in the guild (deleted and re-uploaded, or from another server) is
+    /// looked up by name instead.
+    pub fn resolve(&self, guild_id: Option<GuildId>, spec: &EmojiSpec) -> Option<ReactionType> {
+        match spec {
+            EmojiSpec::Unicode { emoji } => Some(ReactionType::Unicode(emoji.clone())),
+            EmojiSpec::Custom { id, name, .. } => {
+                let guilds = self.guilds.read().unwrap();
+                // DM, or the guild hasn't loaded yet: trust the stored id
+                let Some(emojis) = guild_id.and_then(|guild_id| guilds.get(&guild_id)) else {
+                    return spec.to_reaction();
+                };
+                emojis
+                    .iter()
+                    .find(|emoji| emoji.id.get() == *id)
+                    .or_else(|| named(emojis, name))
+                    .map(GuildEmoji::reaction)
+            }
+            EmojiSpec::Named { names, fallback } => {
+                let names: Vec<&str> = names.iter().map(String::as_str).collect();
+                self.by_names(guild_id, &names, fallback.as_deref())
+            }
+        }
+    }
+}
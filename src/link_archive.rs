@@ -0,0 +1,323 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandInteraction, CommandOptionType, ResolvedValue};
+use serenity::model::channel::Message;
+use serenity::model::id::{ChannelId, GuildId, MessageId, UserId};
+use serenity::prelude::*;
+use sqlx::PgPool;
+
+use crate::commands;
+
+// Small enough that a full page of the longest lines fits in one Discord message
+const PAGE_SIZE: i64 = 5;
+const MAX_TERM_LEN: u16 = 100;
+const MAX_AUTHOR_LEN: usize = 32;
+const MAX_URL_LEN: usize = 150;
+
+/// One link as it appeared in a message, plus its canonical form.
+#[derive(Debug)]
+pub struct ArchivedLink {
+    pub original_url: String,
+    pub canonical_url: String,
+}
+
+pub async fn ensure_table(pool: &PgPool) -> anyhow::Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS shared_links (
+            id            BIGSERIAL PRIMARY KEY,
+            guild_id      BIGINT,
+            channel_id    BIGINT NOT NULL,
+            message_id    BIGINT NOT NULL,
+            author_id     BIGINT NOT NULL,
+            author_name   TEXT NOT NULL,
+            original_url  TEXT NOT NULL,
+            canonical_url TEXT NOT NULL,
+            posted_at     TIMESTAMPTZ NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS shared_links_guild_posted_idx
+         ON shared_links (guild_id, posted_at DESC)",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS shared_links_guild_canonical_idx
+         ON shared_links (guild_id, canonical_url)",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn record(pool: &PgPool, msg: &Message, links: &[ArchivedLink]) -> anyhow::Result<()> {
+    let posted_at = DateTime::<Utc>::from_timestamp(msg.timestamp.unix_timestamp(), 0)
+        .unwrap_or_else(Utc::now);
+    for link in links {
+        sqlx::query(
+            "INSERT INTO shared_links
+                (guild_id, channel_id, message_id, author_id, author_name,
+                 original_url, canonical_url, posted_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+        )
+        .bind(msg.guild_id.map(|g| g.get() as i64))
+        .bind(msg.channel_id.get() as i64)
+        .bind(msg.id.get() as i64)
+        .bind(msg.author.id.get() as i64)
+        .bind(&msg.author.name)
+        .bind(&link.original_url)
+        .bind(&link.canonical_url)
+        .bind(posted_at)
+        .execute(pool)
+        .await?;
+    }
+    Ok(())
+}
+
+struct SearchResult {
+    channel_id: ChannelId,
+    message_id: MessageId,
+    author_name: String,
+    canonical_url: String,
+    posted_at: DateTime<Utc>,
+}
+
+type SearchRow = (i64, i64, String, String, DateTime<Utc>);
+
+async fn search(
+    pool: &PgPool,
+    guild_id: GuildId,
+    term: &str,
+    author: Option<UserId>,
+    since: Option<DateTime<Utc>>,
+    page: i64,
+) -> anyhow::Result<(i64, Vec<SearchResult>)> {
+    // Match the term literally, not as a LIKE pattern
+    let escaped = term.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    let pattern = format!("%{}%", escaped);
+    let filter = "guild_id = $1
+         AND (original_url ILIKE $2 OR canonical_url ILIKE $2)
+         AND ($3::BIGINT IS NULL OR author_id = $3)
+         AND ($4::TIMESTAMPTZ IS NULL OR posted_at >= $4)";
+
+    let (total,) = sqlx::query_as::<_, (i64,)>(&format!(
+        "SELECT COUNT(*) FROM shared_links WHERE {}",
+        filter
+    ))
+    .bind(guild_id.get() as i64)
+    .bind(&pattern)
+    .bind(author.map(|u| u.get() as i64))
+    .bind(since)
+    .fetch_one(pool)
+    .await?;
+
+    let rows = sqlx::query_as::<_, SearchRow>(&format!(
+        "SELECT channel_id, message_id, author_name, canonical_url, posted_at
+         FROM shared_links WHERE {}
+         ORDER BY posted_at DESC
+         LIMIT $5 OFFSET $6",
+        filter
+    ))
+    .bind(guild_id.get() as i64)
+    .bind(&pattern)
+    .bind(author.map(|u| u.get() as i64))
+    .bind(since)
+    .bind(PAGE_SIZE)
+    .bind((page - 1) * PAGE_SIZE)
+    .fetch_all(pool)
+    .await?;
+
+    let results = rows
+        .into_iter()
+        .map(|(channel_id, message_id, author_name, canonical_url, posted_at)| SearchResult {
+            channel_id: ChannelId::new(channel_id as u64),
+            message_id: MessageId::new(message_id as u64),
+            author_name,
+            canonical_url,
+            posted_at,
+        })
+        .collect();
+    Ok((total, results))
+}
+
+/// Accepts a relative window (`12h`, `7d`, `2w`) or a date (`2026-01-31`).
+fn parse_since(input: &str) -> Option<DateTime<Utc>> {
+    let input = input.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+        return date.and_hms_opt(0, 0, 0).map(|dt| dt.and_utc());
+    }
+    let unit = input.chars().last()?;
+    let amount: i64 = input[..input.len() - unit.len_utf8()].parse().ok()?;
+    // Huge amounts are out of range for a Duration; treat them like any other bad input
+    let window = match unit.to_ascii_lowercase() {
+        'h' => Duration::try_hours(amount),
+        'd' => Duration::try_days(amount),
+        'w' => Duration::try_weeks(amount),
+        _ => None,
+    }?;
+    Utc::now().checked_sub_signed(window)
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("links")
+        .description("Search links shared in this server")
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "search", "Find previously shared links")
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::String, "term", "Text to look for in the URL")
+                        .required(true)
+                        .max_length(MAX_TERM_LEN),
+                )
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::User,
+                    "user",
+                    "Only links shared by this member",
+                ))
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "since",
+                    "How far back: 12h, 7d, 2w or a date like 2026-01-31",
+                ))
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::Integer, "page", "Results page")
+                        .min_int_value(1),
+                ),
+        )
+}
+
+async fn handle(command: &CommandInteraction, pool: &PgPool) -> anyhow::Result<String> {
+    let Some(guild_id) = command.guild_id else {
+        return Ok("This only works in a server.".to_string());
+    };
+    let options = command.data.options();
+    let Some(("search", sub_options)) = commands::subcommand(&options) else {
+        return Ok("Unknown subcommand.".to_string());
+    };
+
+    let term = match commands::option(sub_options, "term") {
+        Some(ResolvedValue::String(term)) => term.trim(),
+        _ => "",
+    };
+    let author = match commands::option(sub_options, "user") {
+        Some(ResolvedValue::User(user, _)) => Some(user.id),
+        _ => None,
+    };
+    let since = match commands::option(sub_options, "since") {
+        Some(ResolvedValue::String(since)) => match parse_since(since) {
+            Some(since) => Some(since),
+            None => return Ok(format!("Couldn't understand `{}`. Try 12h, 7d, 2w or 2026-01-31.", since)),
+        },
+        _ => None,
+    };
+    let page = match commands::option(sub_options, "page") {
+        Some(ResolvedValue::Integer(page)) => (*page).max(1),
+        _ => 1,
+    };
+
+    let (total, results) = search(pool, guild_id, term, author, since, page).await?;
+    if total == 0 {
+        return Ok(format!("No shared links match `{}`.", term));
+    }
+    let pages = (total + PAGE_SIZE - 1) / PAGE_SIZE;
+    if results.is_empty() {
+        return Ok(format!("There are only {} page(s) of results.", pages));
+    }
+
+    let mut lines = vec![format!(
+        "🔎 **{} link(s) matching `{}`** — page {} of {}",
+        total, term, page, pages
+    )];
+    lines.extend(results.iter().map(|result| result_line(result, guild_id)));
+    if page < pages {
+        lines.push(format!("Use `page: {}` for more.", page + 1));
+    }
+    Ok(lines.join("\n"))
+}
+
+// Long names and URLs are cut so every row on the page fits; the jump link still leads to
+// the full URL
+fn result_line(result: &SearchResult, guild_id: GuildId) -> String {
+    let author: String = result.author_name.chars().take(MAX_AUTHOR_LEN).collect();
+    let url = if result.canonical_url.chars().count() > MAX_URL_LEN {
+        format!("`{}…`", result.canonical_url.chars().take(MAX_URL_LEN).collect::<String>())
+    } else {
+        format!("<{}>", result.canonical_url)
+    };
+    format!(
+        "<t:{}:d> **{}** in <#{}>: {} ([jump](https://discord.com/channels/{}/{}/{}))",
+        result.posted_at.timestamp(),
+        author,
+        result.channel_id,
+        url,
+        guild_id,
+        result.channel_id,
+        result.message_id
+    )
+}
+
+pub async fn run_command(ctx: &Context, command: &CommandInteraction, pool: &PgPool) -> serenity::Result<()> {
+    let content = match handle(command, pool).await {
+        Ok(content) => content,
+        Err(e) => {
+            eprintln!("[link_archive] Search error: {}", e);
+            "Something went wrong searching links.".to_string()
+        }
+    };
+    commands::reply_ephemeral(ctx, command, content).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_since_windows_and_dates() {
+        let now = Utc::now();
+        let day_ago = parse_since("1d").unwrap();
+        assert!((now - day_ago - Duration::days(1)).num_seconds().abs() < 5);
+        assert!(parse_since("2W").is_some());
+        assert_eq!(
+            parse_since("2026-01-31").map(|since| since.to_rfc3339()),
+            Some("2026-01-31T00:00:00+00:00".to_string())
+        );
+    }
+
+    #[test]
+    fn a_full_page_of_long_results_fits_in_one_message() {
+        let result = SearchResult {
+            channel_id: ChannelId::new(u64::MAX),
+            message_id: MessageId::new(u64::MAX),
+            author_name: "n".repeat(100),
+            canonical_url: format!("https://example.com/{}", "p".repeat(1000)),
+            posted_at: Utc::now(),
+        };
+        let guild_id = GuildId::new(u64::MAX);
+        let line = result_line(&result, guild_id);
+        assert!(line.contains(&"n".repeat(MAX_AUTHOR_LEN)) && !line.contains(&"n".repeat(MAX_AUTHOR_LEN + 1)));
+        assert!(line.contains(&format!("/{}/{}/{}", guild_id, result.channel_id, result.message_id)));
+
+        let header = format!(
+            "🔎 **{} link(s) matching `{}`** — page {} of {}",
+            i64::MAX,
+            "t".repeat(MAX_TERM_LEN as usize),
+            i64::MAX,
+            i64::MAX
+        );
+        let footer = format!("Use `page: {}` for more.", i64::MAX);
+        let page = [header, vec![line; PAGE_SIZE as usize].join("\n"), footer].join("\n");
+        assert!(page.chars().count() <= 2000, "{} characters", page.chars().count());
+    }
+
+    #[test]
+    fn parse_since_rejects_bad_input_without_panicking() {
+        for input in ["", "h", "12", "7x", "-", "99999999999999h", "9999999999999999d", "999999999999999w"] {
+            assert_eq!(parse_since(input), None, "{:?}", input);
+        }
+    }
+}
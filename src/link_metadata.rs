@@ -0,0 +1,416 @@
+use chrono::{DateTime, Utc};
+use futures::future::join_all;
+use reqwest::Client;
+use serde::Deserialize;
+use serenity::async_trait;
+use serenity::builder::{CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter};
+use serenity::model::Timestamp;
+use std::collections::HashMap;
+use std::time::Duration;
+use url::Url;
+
+const FETCH_TIMEOUT_SECS: u64 = 10;
+/// How long a reply waits for all of its link previews together.
+pub const FETCH_ALL_TIMEOUT: Duration = Duration::from_secs(FETCH_TIMEOUT_SECS);
+const EMBED_DESCRIPTION_LIMIT: usize = 4096;
+const EMBED_AUTHOR_LIMIT: usize = 256;
+/// Discord's cap on the text of all embeds in one message, added together.
+pub const EMBED_TOTAL_LIMIT: usize = 6000;
+
+/// What the bot shows for a tweet or Instagram post when the mirror's own preview is missing.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LinkMetadata {
+    pub url: String,
+    pub site: String,
+    pub author: String,
+    #[serde(default)]
+    pub author_handle: Option<String>,
+    #[serde(default)]
+    pub text: String,
+    #[serde(default)]
+    pub media_urls: Vec<String>,
+    #[serde(default)]
+    pub timestamp: Option<DateTime<Utc>>,
+}
+
+impl LinkMetadata {
+    // Instagram authors come from og:title, which can carry the whole caption
+    fn author_name(&self) -> String {
+        let name = match &self.author_handle {
+            Some(handle) => format!("{} (@{})", self.author, handle),
+            None => self.author.clone(),
+        };
+        name.chars().take(EMBED_AUTHOR_LIMIT).collect()
+    }
+
+    fn description(&self) -> String {
+        self.text.chars().take(EMBED_DESCRIPTION_LIMIT).collect()
+    }
+
+    /// Characters this embed counts towards [`EMBED_TOTAL_LIMIT`].
+    pub fn embed_len(&self) -> usize {
+        self.author_name().chars().count() + self.description().chars().count() + self.site.chars().count()
+    }
+
+    pub fn to_embed(&self) -> CreateEmbed {
+        let author_name = self.author_name();
+        let description = self.description();
+
+        let mut embed = CreateEmbed::new()
+            .author(CreateEmbedAuthor::new(author_name))
+            .url(&self.url)
+            .footer(CreateEmbedFooter::new(&self.site))
+            .colour(match self.site.as_str() {
+                "Instagram" => 0xE1306C,
+                _ => 0x1DA1F2,
+            });
+        if !description.is_empty() {
+            embed = embed.description(description);
+        }
+        if let Some(image) = self.media_urls.first() {
+            embed = embed.image(image);
+        }
+        if let Some(timestamp) = self.timestamp.and_then(|t| Timestamp::from_unix_timestamp(t.timestamp()).ok()) {
+            embed = embed.timestamp(timestamp);
+        }
+        embed
+    }
+}
+
+/// Looks up author, text, media and time for a tweet or Instagram URL.
+#[async_trait]
+pub trait MetadataFetcher: Send + Sync {
+    /// Ok(None) means the URL isn't something this fetcher understands.
+    async fn fetch(&self, url: &str) -> anyhow::Result<Option<LinkMetadata>>;
+}
+
+fn is_twitter_host(host: &str) -> bool {
+    matches!(host, "x.com" | "twitter.com" | "www.x.com" | "www.twitter.com" | "mobile.twitter.com")
+}
+
+fn is_instagram_host(host: &str) -> bool {
+    host == "instagram.com" || host.ends_with(".instagram.com")
+}
+
+#[derive(Debug, Deserialize)]
+struct FxTwitterResponse {
+    tweet: Option<FxTweet>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FxTweet {
+    url: String,
+    #[serde(default)]
+    text: String,
+    created_timestamp: Option<i64>,
+    author: FxAuthor,
+    media: Option<FxMedia>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FxAuthor {
+    name: String,
+    screen_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct FxMedia {
+    #[serde(default)]
+    all: Vec<FxMediaItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FxMediaItem {
+    url: Option<String>,
+    thumbnail_url: Option<String>,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+}
+
+/// Live implementation: tweets go through the FxTwitter API, Instagram posts through their
+/// OpenGraph tags.
+pub struct HttpMetadataFetcher {
+    client: Client,
+}
+
+impl HttpMetadataFetcher {
+    pub fn new() -> anyhow::Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(FETCH_TIMEOUT_SECS))
+            .build()?;
+        Ok(HttpMetadataFetcher { client })
+    }
+
+    async fn fetch_tweet(&self, url: &Url) -> anyhow::Result<Option<LinkMetadata>> {
+        // Expect /{user}/status/{id}
+        let segments: Vec<&str> = url.path_segments().map(|s| s.collect()).unwrap_or_default();
+        let (user, id) = match segments.as_slice() {
+            [user, "status", id, ..] => (*user, *id),
+            _ => return Ok(None),
+        };
+
+        let resp: FxTwitterResponse = self
+            .client
+            .get(format!("https://api.fxtwitter.com/{}/status/{}", user, id))
+            .header("User-Agent", "MadiBot/1.0")
+            .send()
+            .await?
+            .json()
+            .await?;
+        let Some(tweet) = resp.tweet else {
+            return Ok(None);
+        };
+
+        let media_urls = tweet
+            .media
+            .map(|media| {
+                media
+                    .all
+                    .into_iter()
+                    .filter_map(|item| match item.kind.as_deref() {
+                        Some("photo") => item.url,
+                        _ => item.thumbnail_url.or(item.url),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(Some(LinkMetadata {
+            url: tweet.url,
+            site: "X".to_string(),
+            author: tweet.author.name,
+            author_handle: Some(tweet.author.screen_name),
+            text: tweet.text,
+            media_urls,
+            timestamp: tweet.created_timestamp.and_then(|t| DateTime::from_timestamp(t, 0)),
+        }))
+    }
+
+    async fn fetch_open_graph(&self, url: &Url) -> anyhow::Result<Option<LinkMetadata>> {
+        let html = self
+            .client
+            .get(url.as_str())
+            .header("User-Agent", "Mozilla/5.0 (compatible; Discordbot/2.0; +https://discordapp.com)")
+            .send()
+            .await?
+            .text()
+            .await?;
+
+        let document = scraper::Html::parse_document(&html);
+        let selector = scraper::Selector::parse("meta[property]").unwrap();
+        let tags: HashMap<&str, &str> = document
+            .select(&selector)
+            .filter_map(|el| Some((el.value().attr("property")?, el.value().attr("content")?)))
+            .collect();
+
+        let Some(title) = tags.get("og:title") else {
+            return Ok(None);
+        };
+        Ok(Some(LinkMetadata {
+            url: url.to_string(),
+            site: "Instagram".to_string(),
+            author: title.to_string(),
+            author_handle: None,
+            text: tags.get("og:description").map(|d| d.to_string()).unwrap_or_default(),
+            media_urls: tags.get("og:image").map(|i| vec![i.to_string()]).unwrap_or_default(),
+            timestamp: tags
+                .get("article:published_time")
+                .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+                .map(|t| t.with_timezone(&Utc)),
+        }))
+    }
+}
+
+#[async_trait]
+impl MetadataFetcher for HttpMetadataFetcher {
+    async fn fetch(&self, url: &str) -> anyhow::Result<Option<LinkMetadata>> {
+        let parsed = Url::parse(url)?;
+        let host = parsed.host_str().unwrap_or_default().to_lowercase();
+        if is_twitter_host(&host) {
+            self.fetch_tweet(&parsed).await
+        } else if is_instagram_host(&host) {
+            self.fetch_open_graph(&parsed).await
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Fetch every URL at once, giving up on whatever hasn't answered by `timeout`. Results line
+/// up with `urls`; errors and timeouts are logged and come back as None.
+pub async fn fetch_many(fetcher: &dyn MetadataFetcher, urls: &[&str], timeout: Duration) -> Vec<Option<LinkMetadata>> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    join_all(urls.iter().map(|url| async move {
+        match tokio::time::timeout_at(deadline, fetcher.fetch(url)).await {
+            Ok(Ok(metadata)) => metadata,
+            Ok(Err(e)) => {
+                println!("[link_metadata] Error fetching metadata for {}: {:?}", url, e);
+                None
+            }
+            Err(_) => {
+                println!("[link_metadata] Timed out fetching metadata for {}", url);
+                None
+            }
+        }
+    }))
+    .await
+}
+
+/// Canned metadata keyed by URL, loaded from a JSON object of `url → LinkMetadata`. Lets the
+/// embed rendering be exercised without hitting the network.
+pub struct FixtureMetadataFetcher {
+    fixtures: HashMap<String, LinkMetadata>,
+}
+
+impl FixtureMetadataFetcher {
+    pub fn new(fixtures: HashMap<String, LinkMetadata>) -> Self {
+        FixtureMetadataFetcher { fixtures }
+    }
+
+    pub fn from_file(path: &str) -> anyhow::Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        Ok(Self::new(serde_json::from_str(&json)?))
+    }
+}
+
+#[async_trait]
+impl MetadataFetcher for FixtureMetadataFetcher {
+    async fn fetch(&self, url: &str) -> anyhow::Result<Option<LinkMetadata>> {
+        Ok(self.fixtures.get(url).cloned())
+    }
+}
+
+/// The HTTP fetcher, or the fixture file named by LINK_METADATA_FIXTURES when set.
+pub fn from_env() -> Box<dyn MetadataFetcher> {
+    if let Ok(path) = std::env::var("LINK_METADATA_FIXTURES") {
+        match FixtureMetadataFetcher::from_file(&path) {
+            Ok(fetcher) => {
+                println!("[link_metadata] Using fixtures from {}", path);
+                return Box::new(fetcher);
+            }
+            Err(e) => eprintln!("[link_metadata] Failed to load fixtures from {}: {}", path, e),
+        }
+    }
+    match HttpMetadataFetcher::new() {
+        Ok(fetcher) => Box::new(fetcher),
+        Err(e) => {
+            eprintln!("[link_metadata] Failed to build HTTP client, embeds disabled: {}", e);
+            Box::new(FixtureMetadataFetcher::new(HashMap::new()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn tweet() -> LinkMetadata {
+        LinkMetadata {
+            url: "https://x.com/sunfc/status/1".to_string(),
+            site: "X".to_string(),
+            author: "Sun FC".to_string(),
+            author_handle: Some("sunfc".to_string()),
+            text: "Matchday ☀️".to_string(),
+            media_urls: vec!["https://pbs.example/1.jpg".to_string(), "https://pbs.example/2.jpg".to_string()],
+            timestamp: DateTime::parse_from_rfc3339("2026-03-01T19:00:00Z").ok().map(|t| t.with_timezone(&Utc)),
+        }
+    }
+
+    fn fixtures() -> FixtureMetadataFetcher {
+        let post = LinkMetadata {
+            url: "https://www.instagram.com/p/abc/".to_string(),
+            site: "Instagram".to_string(),
+            author: "sunfc".to_string(),
+            author_handle: None,
+            text: String::new(),
+            media_urls: Vec::new(),
+            timestamp: None,
+        };
+        FixtureMetadataFetcher::new(HashMap::from([
+            ("https://x.com/sunfc/status/1".to_string(), tweet()),
+            ("https://www.instagram.com/p/abc/".to_string(), post),
+        ]))
+    }
+
+    fn embed_json(metadata: &LinkMetadata) -> Value {
+        serde_json::to_value(metadata.to_embed()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn tweet_fixture_renders_a_full_embed() {
+        let metadata = fixtures().fetch("https://x.com/sunfc/status/1").await.unwrap().unwrap();
+        let embed = embed_json(&metadata);
+        assert_eq!(embed["author"]["name"], json!("Sun FC (@sunfc)"));
+        assert_eq!(embed["url"], json!("https://x.com/sunfc/status/1"));
+        assert_eq!(embed["description"], json!("Matchday ☀️"));
+        assert_eq!(embed["footer"]["text"], json!("X"));
+        assert_eq!(embed["color"], json!(0x1DA1F2));
+        assert_eq!(embed["image"]["url"], json!("https://pbs.example/1.jpg"));
+        assert!(embed["timestamp"].as_str().unwrap().starts_with("2026-03-01T19:00:00"));
+    }
+
+    #[tokio::test]
+    async fn instagram_fixture_leaves_out_missing_parts() {
+        let metadata = fixtures().fetch("https://www.instagram.com/p/abc/").await.unwrap().unwrap();
+        let embed = embed_json(&metadata);
+        assert_eq!(embed["author"]["name"], json!("sunfc"));
+        assert_eq!(embed["color"], json!(0xE1306C));
+        for missing in ["description", "image", "timestamp"] {
+            assert!(embed.get(missing).is_none_or(Value::is_null), "{}", missing);
+        }
+    }
+
+    #[test]
+    fn long_text_is_cut_to_the_embed_limit() {
+        let mut metadata = tweet();
+        metadata.text = "a".repeat(EMBED_DESCRIPTION_LIMIT + 100);
+        let embed = embed_json(&metadata);
+        assert_eq!(embed["description"].as_str().unwrap().len(), EMBED_DESCRIPTION_LIMIT);
+    }
+
+    #[test]
+    fn long_author_is_cut_to_the_embed_limit() {
+        let mut metadata = tweet();
+        metadata.author = "b".repeat(EMBED_AUTHOR_LIMIT * 2);
+        metadata.author_handle = None;
+        let embed = embed_json(&metadata);
+        assert_eq!(embed["author"]["name"].as_str().unwrap().len(), EMBED_AUTHOR_LIMIT);
+        assert_eq!(metadata.embed_len(), EMBED_AUTHOR_LIMIT + "Matchday ☀️".chars().count() + "X".len());
+    }
+
+    #[tokio::test]
+    async fn unknown_urls_have_no_metadata() {
+        assert!(fixtures().fetch("https://x.com/other/status/2").await.unwrap().is_none());
+    }
+
+    /// Answers from the fixtures, except for URLs containing "slow", which never answer.
+    struct SlowFetcher(FixtureMetadataFetcher);
+
+    #[async_trait]
+    impl MetadataFetcher for SlowFetcher {
+        async fn fetch(&self, url: &str) -> anyhow::Result<Option<LinkMetadata>> {
+            if url.contains("slow") {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+            }
+            self.0.fetch(url).await
+        }
+    }
+
+    #[tokio::test]
+    async fn fetch_many_keeps_order_and_shares_one_deadline() {
+        let fetcher = SlowFetcher(fixtures());
+        let urls = [
+            "https://x.com/slow/status/1",
+            "https://x.com/sunfc/status/1",
+            "https://x.com/slow/status/2",
+            "https://www.instagram.com/p/abc/",
+        ];
+        let started = std::time::Instant::now();
+        let results = fetch_many(&fetcher, &urls, Duration::from_millis(200)).await;
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        let sites: Vec<Option<&str>> = results.iter().map(|m| m.as_ref().map(|m| m.site.as_str())).collect();
+        assert_eq!(sites, [None, Some("X"), None, Some("Instagram")]);
+    }
+}
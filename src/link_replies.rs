@@ -0,0 +1,82 @@
+use serenity::model::id::{ChannelId, MessageId};
+use sqlx::PgPool;
+
+/// A bot reply carrying the rewritten links for one source message.
+#[derive(Debug)]
+pub struct TrackedReply {
+    pub channel_id: ChannelId,
+    pub reply_id: MessageId,
+    pub content: String,
+}
+
+pub async fn ensure_table(pool: &PgPool) -> anyhow::Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS link_rewrite_replies (
+            source_message_id BIGINT PRIMARY KEY,
+            channel_id        BIGINT NOT NULL,
+            reply_message_id  BIGINT NOT NULL,
+            content           TEXT NOT NULL,
+            created_at        TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn record(
+    pool: &PgPool,
+    source_id: MessageId,
+    channel_id: ChannelId,
+    reply_id: MessageId,
+    content: &str,
+) -> anyhow::Result<()> {
+    sqlx::query(
+        "INSERT INTO link_rewrite_replies (source_message_id, channel_id, reply_message_id, content)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (source_message_id) DO UPDATE
+         SET reply_message_id = EXCLUDED.reply_message_id, content = EXCLUDED.content",
+    )
+    .bind(source_id.get() as i64)
+    .bind(channel_id.get() as i64)
+    .bind(reply_id.get() as i64)
+    .bind(content)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn find(pool: &PgPool, source_id: MessageId) -> anyhow::Result<Option<TrackedReply>> {
+    let row = sqlx::query_as::<_, (i64, i64, String)>(
+        "SELECT channel_id, reply_message_id, content
+         FROM link_rewrite_replies WHERE source_message_id = $1",
+    )
+    .bind(source_id.get() as i64)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|(channel_id, reply_id, content)| TrackedReply {
+        channel_id: ChannelId::new(channel_id as u64),
+        reply_id: MessageId::new(reply_id as u64),
+        content,
+    }))
+}
+
+pub async fn update_content(pool: &PgPool, source_id: MessageId, content: &str) -> anyhow::Result<()> {
+    sqlx::query("UPDATE link_rewrite_replies SET content = $2 WHERE source_message_id = $1")
+        .bind(source_id.get() as i64)
+        .bind(content)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn forget(pool: &PgPool, source_id: MessageId) -> anyhow::Result<()> {
+    sqlx::query("DELETE FROM link_rewrite_replies WHERE source_message_id = $1")
+        .bind(source_id.get() as i64)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,407 @@
+use crate::mirror_health::MirrorHealth;
+use sqlx::PgPool;
+use url::Url;
+
+/// One row of the rewrite table: which hosts to match and where to send them.
+#[derive(Debug, Clone)]
+pub struct RewriteRule {
+    pub name: String,
+    /// Registrable hosts this rule matches, e.g. `x.com`, `twitter.com`.
+    pub source_hosts: Vec<String>,
+    /// Subdomain labels accepted in front of a source host (`www`, `mobile`).
+    /// `*` accepts any subdomain. Empty means the bare host only.
+    pub subdomains: Vec<String>,
+    /// Keep the matched subdomain on the target host (`www.instagram.com` →
+    /// `www.zzinstagram.com`) instead of dropping it.
+    pub keep_subdomain: bool,
+    /// Mirror hosts in order of preference; the first healthy one is used.
+    pub mirror_hosts: Vec<String>,
+    /// Optional path prefix swap applied after the host rewrite.
+    pub path_rewrite: Option<(String, String)>,
+}
+
+impl RewriteRule {
+    fn new(name: &str, source_hosts: &[&str], mirror_hosts: &[&str]) -> Self {
+        RewriteRule {
+            name: name.to_string(),
+            source_hosts: source_hosts.iter().map(|h| h.to_string()).collect(),
+            subdomains: Vec::new(),
+            keep_subdomain: false,
+            mirror_hosts: mirror_hosts.iter().map(|h| h.to_string()).collect(),
+            path_rewrite: None,
+        }
+    }
+
+    fn with_subdomains(mut self, subdomains: &[&str], keep: bool) -> Self {
+        self.subdomains = subdomains.iter().map(|s| s.to_string()).collect();
+        self.keep_subdomain = keep;
+        self
+    }
+
+    /// If `host` (already lowercased) belongs to this rule, returns the subdomain to carry
+    /// over to the mirror, if any.
+    fn match_host<'a>(&self, host: &'a str) -> Option<Option<&'a str>> {
+        for source in &self.source_hosts {
+            if host == source {
+                return Some(None);
+            }
+            let Some(sub) = host.strip_suffix(source.as_str()).and_then(|h| h.strip_suffix('.')) else {
+                continue;
+            };
+            if sub.is_empty() {
+                continue;
+            }
+            let allowed = self.subdomains.iter().any(|s| s == "*" || s == sub);
+            if allowed {
+                return Some(self.keep_subdomain.then_some(sub));
+            }
+        }
+        None
+    }
+
+    fn rewrite_path(&self, path: &str) -> String {
+        match &self.path_rewrite {
+            Some((from, to)) if path.starts_with(from.as_str()) => {
+                format!("{}{}", to, &path[from.len()..])
+            }
+            _ => path.to_string(),
+        }
+    }
+}
+
+/// The rules the bot shipped with before the table existed.
+pub fn default_rules() -> Vec<RewriteRule> {
+    vec![
+        RewriteRule::new("twitter", &["x.com", "twitter.com"], &["xcancel.com", "fixupx.com"]),
+        RewriteRule::new("instagram", &["instagram.com"], &["zzinstagram.com", "kkinstagram.com"])
+            .with_subdomains(&["www"], true),
+    ]
+}
+
+#[derive(Debug, Clone)]
+pub struct LinkRewriter {
+    rules: Vec<RewriteRule>,
+    health: MirrorHealth,
+}
+
+impl LinkRewriter {
+    pub fn new(rules: Vec<RewriteRule>, health: MirrorHealth) -> Self {
+        LinkRewriter { rules, health }
+    }
+
+    pub fn rules(&self) -> &[RewriteRule] {
+        &self.rules
+    }
+
+    /// Every mirror host across all rules, for the health prober.
+    pub fn mirror_hosts(&self) -> Vec<String> {
+        let mut hosts: Vec<String> = Vec::new();
+        for host in self.rules.iter().flat_map(|r| &r.mirror_hosts) {
+            if !hosts.contains(host) {
+                hosts.push(host.clone());
+            }
+        }
+        hosts
+    }
+
+    /// The first mirror for `rule` that isn't known to be down. Falls back to the preferred
+    /// mirror when every one of them is down.
+    pub fn pick_mirror<'a>(&self, rule: &'a RewriteRule) -> Option<&'a str> {
+        rule.mirror_hosts
+            .iter()
+            .find(|host| self.health.is_up(host))
+            .or_else(|| rule.mirror_hosts.first())
+            .map(String::as_str)
+    }
+
+    /// Rewrite a single URL. Returns the rule name and new URL, or None if no rule applies.
+    pub fn rewrite_url(&self, url: &str) -> Option<(&str, String)> {
+        self.rewrite_with(url, |rule| self.pick_mirror(rule))
+    }
+
+    /// A stable form of `url` for archiving and duplicate checks: rewritten to the rule's
+    /// preferred mirror whatever its health, with no fragment or trailing slash.
+    pub fn canonical_url(&self, url: &str) -> String {
+        let rewritten = self
+            .rewrite_with(url, |rule| rule.mirror_hosts.first().map(String::as_str))
+            .map(|(_, rewritten)| rewritten);
+        let source = rewritten.as_deref().unwrap_or(url);
+        match Url::parse(source) {
+            Ok(mut parsed) => {
+                parsed.set_fragment(None);
+                let mut canonical = parsed.to_string();
+                if parsed.query().is_none() && canonical.ends_with('/') {
+                    canonical.pop();
+                }
+                canonical
+            }
+            Err(_) => source.to_string(),
+        }
+    }
+
+    fn rewrite_with<'a>(
+        &'a self,
+        url: &str,
+        pick_mirror: impl Fn(&'a RewriteRule) -> Option<&'a str>,
+    ) -> Option<(&'a str, String)> {
+        let lower = url.to_lowercase();
+        let scheme_len = if lower.starts_with("https://") {
+            "https://".len()
+        } else if lower.starts_with("http://") {
+            "http://".len()
+        } else {
+            return None;
+        };
+
+        let rest = &url[scheme_len..];
+        let host_end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+        let host = rest[..host_end].to_lowercase();
+        let path = &rest[host_end..];
+
+        self.rules.iter().find_map(|rule| {
+            let subdomain = rule.match_host(&host)?;
+            let mirror = pick_mirror(rule)?;
+            let new_host = match subdomain {
+                Some(sub) => format!("{}.{}", sub, mirror),
+                None => mirror.to_string(),
+            };
+            Some((
+                rule.name.as_str(),
+                format!("{}{}{}", &url[..scheme_len], new_host, rule.rewrite_path(path)),
+            ))
+        })
+    }
+}
+
+pub async fn ensure_table(pool: &PgPool) -> anyhow::Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS link_rewrite_rules (
+            name           TEXT PRIMARY KEY,
+            source_hosts   TEXT[] NOT NULL,
+            subdomains     TEXT[] NOT NULL DEFAULT '{}',
+            keep_subdomain BOOLEAN NOT NULL DEFAULT FALSE,
+            target_host    TEXT NOT NULL,
+            path_from      TEXT,
+            path_to        TEXT,
+            enabled        BOOLEAN NOT NULL DEFAULT TRUE
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Added with mirror fallback; target_host stays the preferred mirror
+    sqlx::query(
+        "ALTER TABLE link_rewrite_rules
+         ADD COLUMN IF NOT EXISTS fallback_hosts TEXT[] NOT NULL DEFAULT '{}'",
+    )
+    .execute(pool)
+    .await?;
+
+    // Seed the built-in rules; existing rows (including edited ones) are left alone.
+    for rule in default_rules() {
+        let (path_from, path_to) = rule.path_rewrite.clone().unzip();
+        sqlx::query(
+            "INSERT INTO link_rewrite_rules
+                (name, source_hosts, subdomains, keep_subdomain, target_host, fallback_hosts,
+                 path_from, path_to)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+             ON CONFLICT (name) DO NOTHING",
+        )
+        .bind(&rule.name)
+        .bind(&rule.source_hosts)
+        .bind(&rule.subdomains)
+        .bind(rule.keep_subdomain)
+        .bind(&rule.mirror_hosts[0])
+        .bind(&rule.mirror_hosts[1..])
+        .bind(path_from)
+        .bind(path_to)
+        .execute(pool)
+        .await?;
+    }
+    Ok(())
+}
+
+type RuleRow = (
+    String,
+    Vec<String>,
+    Vec<String>,
+    bool,
+    String,
+    Vec<String>,
+    Option<String>,
+    Option<String>,
+);
+
+async fn fetch_rules(pool: &PgPool) -> anyhow::Result<Vec<RewriteRule>> {
+    let rows = sqlx::query_as::<_, RuleRow>(
+        "SELECT name, source_hosts, subdomains, keep_subdomain, target_host, fallback_hosts,
+                path_from, path_to
+         FROM link_rewrite_rules
+         WHERE enabled
+         ORDER BY name",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(
+            |(
+                name,
+                source_hosts,
+                subdomains,
+                keep_subdomain,
+                target_host,
+                fallback_hosts,
+                path_from,
+                path_to,
+            )| {
+                let mirror_hosts = std::iter::once(target_host)
+                    .chain(fallback_hosts)
+                    .map(|h| h.to_lowercase())
+                    .collect();
+                RewriteRule {
+                    name,
+                    source_hosts: source_hosts.into_iter().map(|h| h.to_lowercase()).collect(),
+                    subdomains: subdomains.into_iter().map(|s| s.to_lowercase()).collect(),
+                    keep_subdomain,
+                    mirror_hosts,
+                    path_rewrite: path_from.zip(path_to),
+                }
+            },
+        )
+        .collect())
+}
+
+/// Load the rule table, falling back to the built-in rules if the database is unavailable.
+pub async fn load(pool: &PgPool, health: MirrorHealth) -> LinkRewriter {
+    let loaded = async {
+        ensure_table(pool).await?;
+        fetch_rules(pool).await
+    }
+    .await;
+
+    match loaded {
+        Ok(rules) => {
+            println!("[link_rewriter] Loaded {} rewrite rule(s)", rules.len());
+            LinkRewriter::new(rules, health)
+        }
+        Err(e) => {
+            eprintln!("[link_rewriter] Failed to load rules, using defaults: {}", e);
+            LinkRewriter::new(default_rules(), health)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mirror_health::MirrorStatus;
+    use crate::url_sanitizer::UrlSanitizer;
+    use chrono::Utc;
+
+    fn rewriter() -> LinkRewriter {
+        LinkRewriter::new(default_rules(), MirrorHealth::default())
+    }
+
+    fn rewrite(rewriter: &LinkRewriter, url: &str) -> Option<String> {
+        rewriter.rewrite_url(url).map(|(_, rewritten)| rewritten)
+    }
+
+    #[test]
+    fn twitter_and_x_go_to_the_preferred_mirror() {
+        let rewriter = rewriter();
+        for (url, expected) in [
+            ("https://x.com/sunfc/status/1", "https://xcancel.com/sunfc/status/1"),
+            ("https://twitter.com/sunfc/status/1", "https://xcancel.com/sunfc/status/1"),
+            ("http://X.com/sunfc", "http://xcancel.com/sunfc"),
+            ("https://x.com", "https://xcancel.com"),
+        ] {
+            assert_eq!(rewrite(&rewriter, url).as_deref(), Some(expected), "{}", url);
+        }
+        assert_eq!(rewriter.rewrite_url("https://x.com/a").map(|(rule, _)| rule), Some("twitter"));
+    }
+
+    #[test]
+    fn instagram_keeps_www() {
+        let rewriter = rewriter();
+        for (url, expected) in [
+            ("https://instagram.com/p/abc/", "https://zzinstagram.com/p/abc/"),
+            ("https://www.instagram.com/p/abc/", "https://www.zzinstagram.com/p/abc/"),
+        ] {
+            assert_eq!(rewrite(&rewriter, url).as_deref(), Some(expected), "{}", url);
+        }
+    }
+
+    #[test]
+    fn subdomains_need_to_be_allowed() {
+        let rewriter = rewriter();
+        // twitter allows no subdomains, instagram only www
+        assert_eq!(rewrite(&rewriter, "https://mobile.x.com/a"), None);
+        assert_eq!(rewrite(&rewriter, "https://help.instagram.com/a"), None);
+        // A different host that happens to end the same way
+        assert_eq!(rewrite(&rewriter, "https://notx.com/a"), None);
+        assert_eq!(rewrite(&rewriter, "https://example.com/a"), None);
+        assert_eq!(rewrite(&rewriter, "ftp://x.com/a"), None);
+
+        let wildcard = LinkRewriter::new(
+            vec![RewriteRule::new("reddit", &["reddit.com"], &["rxddit.com"]).with_subdomains(&["*"], false)],
+            MirrorHealth::default(),
+        );
+        assert_eq!(
+            rewrite(&wildcard, "https://old.reddit.com/r/nwsl").as_deref(),
+            Some("https://rxddit.com/r/nwsl")
+        );
+    }
+
+    #[test]
+    fn path_rewrite_swaps_the_prefix() {
+        let mut rule = RewriteRule::new("bluesky", &["bsky.app"], &["bskx.app"]);
+        rule.path_rewrite = Some(("/profile/".to_string(), "/p/".to_string()));
+        let rewriter = LinkRewriter::new(vec![rule], MirrorHealth::default());
+        assert_eq!(
+            rewrite(&rewriter, "https://bsky.app/profile/sunfc/post/1").as_deref(),
+            Some("https://bskx.app/p/sunfc/post/1")
+        );
+        assert_eq!(rewrite(&rewriter, "https://bsky.app/search").as_deref(), Some("https://bskx.app/search"));
+    }
+
+    #[test]
+    fn queries_survive_the_rewrite_and_tracking_is_stripped_first() {
+        let rewriter = rewriter();
+        assert_eq!(
+            rewrite(&rewriter, "https://x.com/a/status/1?lang=en#top").as_deref(),
+            Some("https://xcancel.com/a/status/1?lang=en#top")
+        );
+
+        let sanitizer = UrlSanitizer::new([("x.com", "s"), ("*", "utm_*")]);
+        let cleaned = sanitizer.clean("https://x.com/a/status/1?s=20&utm_source=ig&lang=en").unwrap();
+        assert_eq!(rewrite(&rewriter, &cleaned).as_deref(), Some("https://xcancel.com/a/status/1?lang=en"));
+
+        assert_eq!(rewriter.canonical_url("https://x.com/a/status/1/#top"), "https://xcancel.com/a/status/1");
+    }
+
+    #[test]
+    fn falls_back_to_the_next_healthy_mirror() {
+        let health = MirrorHealth::default();
+        let rewriter = LinkRewriter::new(default_rules(), health.clone());
+        let status = |up| MirrorStatus {
+            up,
+            checked_at: Utc::now(),
+            detail: String::new(),
+        };
+
+        health.record("xcancel.com", status(false));
+        assert_eq!(rewrite(&rewriter, "https://x.com/a").as_deref(), Some("https://fixupx.com/a"));
+
+        // Everything down: stick with the preferred mirror
+        health.record("fixupx.com", status(false));
+        assert_eq!(rewrite(&rewriter, "https://x.com/a").as_deref(), Some("https://xcancel.com/a"));
+
+        // Canonical links ignore health entirely
+        health.record("xcancel.com", status(true));
+        assert_eq!(rewriter.canonical_url("https://twitter.com/a"), "https://xcancel.com/a");
+    }
+}
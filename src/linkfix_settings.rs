@@ -0,0 +1,362 @@
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandInteraction, CommandOptionType, ResolvedValue};
+use serenity::model::channel::ChannelType;
+use serenity::model::id::{ChannelId, GuildId, UserId};
+use serenity::model::Permissions;
+use serenity::prelude::*;
+use sqlx::PgPool;
+
+use crate::commands;
+use crate::duplicate_links::DuplicateMode;
+
+const DEFAULT_DUPLICATE_WINDOW_HOURS: i32 = 24;
+
+pub async fn ensure_tables(pool: &PgPool) -> anyhow::Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS linkfix_guild_settings (
+            guild_id   BIGINT PRIMARY KEY,
+            enabled    BOOLEAN NOT NULL DEFAULT TRUE,
+            updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "ALTER TABLE linkfix_guild_settings
+         ADD COLUMN IF NOT EXISTS duplicate_mode TEXT NOT NULL DEFAULT 'off',
+         ADD COLUMN IF NOT EXISTS duplicate_window_hours INTEGER NOT NULL DEFAULT 24",
+    )
+    .execute(pool)
+    .await?;
+
+    // Flagging repeated links is opt-in, including for guilds whose row comes from other
+    // settings
+    sqlx::query("ALTER TABLE linkfix_guild_settings ALTER COLUMN duplicate_mode SET DEFAULT 'off'")
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS linkfix_channel_settings (
+            channel_id BIGINT PRIMARY KEY,
+            guild_id   BIGINT NOT NULL,
+            enabled    BOOLEAN NOT NULL,
+            updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS linkfix_user_settings (
+            user_id    BIGINT PRIMARY KEY,
+            enabled    BOOLEAN NOT NULL,
+            updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Whether the bot should repost links for this message. A channel setting overrides the
+/// guild setting; a user who opted out is skipped everywhere.
+pub async fn is_enabled(
+    pool: &PgPool,
+    guild_id: Option<GuildId>,
+    channel_id: ChannelId,
+    user_id: UserId,
+) -> anyhow::Result<bool> {
+    let (enabled,) = sqlx::query_as::<_, (bool,)>(
+        "SELECT COALESCE(
+                    (SELECT enabled FROM linkfix_channel_settings WHERE channel_id = $2),
+                    (SELECT enabled FROM linkfix_guild_settings WHERE guild_id = $1),
+                    TRUE)
+                AND COALESCE(
+                    (SELECT enabled FROM linkfix_user_settings WHERE user_id = $3),
+                    TRUE)",
+    )
+    .bind(guild_id.map(|g| g.get() as i64))
+    .bind(channel_id.get() as i64)
+    .bind(user_id.get() as i64)
+    .fetch_one(pool)
+    .await?;
+    Ok(enabled)
+}
+
+async fn set_guild(pool: &PgPool, guild_id: GuildId, enabled: bool) -> anyhow::Result<()> {
+    sqlx::query(
+        "INSERT INTO linkfix_guild_settings (guild_id, enabled) VALUES ($1, $2)
+         ON CONFLICT (guild_id) DO UPDATE SET enabled = EXCLUDED.enabled, updated_at = NOW()",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(enabled)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// How the guild wants repeated links flagged, and how far back to look. Off until an
+/// admin picks a mode.
+pub async fn duplicate_settings(pool: &PgPool, guild_id: GuildId) -> anyhow::Result<(DuplicateMode, i32)> {
+    let row = sqlx::query_as::<_, (String, i32)>(
+        "SELECT duplicate_mode, duplicate_window_hours FROM linkfix_guild_settings WHERE guild_id = $1",
+    )
+    .bind(guild_id.get() as i64)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(match row {
+        Some((mode, hours)) => (DuplicateMode::parse(&mode).unwrap_or(DuplicateMode::Off), hours),
+        None => (DuplicateMode::Off, DEFAULT_DUPLICATE_WINDOW_HOURS),
+    })
+}
+
+async fn set_duplicate_settings(
+    pool: &PgPool,
+    guild_id: GuildId,
+    mode: DuplicateMode,
+    window_hours: Option<i32>,
+) -> anyhow::Result<()> {
+    sqlx::query(
+        "INSERT INTO linkfix_guild_settings (guild_id, duplicate_mode, duplicate_window_hours)
+         VALUES ($1, $2, COALESCE($3, $4))
+         ON CONFLICT (guild_id) DO UPDATE
+         SET duplicate_mode = EXCLUDED.duplicate_mode,
+             duplicate_window_hours = COALESCE($3, linkfix_guild_settings.duplicate_window_hours),
+             updated_at = NOW()",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(mode.as_str())
+    .bind(window_hours)
+    .bind(DEFAULT_DUPLICATE_WINDOW_HOURS)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+async fn set_channel(
+    pool: &PgPool,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    enabled: bool,
+) -> anyhow::Result<()> {
+    sqlx::query(
+        "INSERT INTO linkfix_channel_settings (channel_id, guild_id, enabled) VALUES ($1, $2, $3)
+         ON CONFLICT (channel_id) DO UPDATE SET enabled = EXCLUDED.enabled, updated_at = NOW()",
+    )
+    .bind(channel_id.get() as i64)
+    .bind(guild_id.get() as i64)
+    .bind(enabled)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+async fn set_user(pool: &PgPool, user_id: UserId, enabled: bool) -> anyhow::Result<()> {
+    sqlx::query(
+        "INSERT INTO linkfix_user_settings (user_id, enabled) VALUES ($1, $2)
+         ON CONFLICT (user_id) DO UPDATE SET enabled = EXCLUDED.enabled, updated_at = NOW()",
+    )
+    .bind(user_id.get() as i64)
+    .bind(enabled)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+async fn disabled_channels(pool: &PgPool, guild_id: GuildId) -> anyhow::Result<Vec<ChannelId>> {
+    let rows = sqlx::query_as::<_, (i64,)>(
+        "SELECT channel_id FROM linkfix_channel_settings
+         WHERE guild_id = $1 AND NOT enabled
+         ORDER BY channel_id",
+    )
+    .bind(guild_id.get() as i64)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(|(id,)| ChannelId::new(id as u64)).collect())
+}
+
+fn on_off_option() -> CreateCommandOption {
+    CreateCommandOption::new(CommandOptionType::String, "state", "Turn link fixing on or off")
+        .add_string_choice("on", "on")
+        .add_string_choice("off", "off")
+        .required(true)
+}
+
+fn channel_option() -> CreateCommandOption {
+    CreateCommandOption::new(CommandOptionType::Channel, "channel", "Defaults to this channel")
+        .channel_types(vec![ChannelType::Text, ChannelType::News, ChannelType::PublicThread])
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("linkfix")
+        .description("Control xcancel/zzinstagram link reposts")
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "disable", "Stop link reposts in a channel")
+                .add_sub_option(channel_option()),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "enable", "Allow link reposts in a channel")
+                .add_sub_option(channel_option()),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "server", "Turn link reposts on or off server-wide")
+                .add_sub_option(on_off_option()),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "me", "Turn link reposts on or off for your messages")
+                .add_sub_option(on_off_option()),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "duplicates", "How to flag links that were already posted")
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::String, "mode", "Reply with a jump link, react, or do nothing")
+                        .add_string_choice("reply", "reply")
+                        .add_string_choice("react", "react")
+                        .add_string_choice("off", "off")
+                        .required(true),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::Integer, "window_hours", "How far back to look")
+                        .min_int_value(1)
+                        .max_int_value(24 * 30),
+                ),
+        )
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "status",
+            "Show link repost settings here",
+        ))
+}
+
+fn has_permission(command: &CommandInteraction, permission: Permissions) -> bool {
+    command
+        .member
+        .as_ref()
+        .and_then(|member| member.permissions)
+        .is_some_and(|p| p.contains(permission) || p.administrator())
+}
+
+async fn handle(command: &CommandInteraction, pool: &PgPool) -> anyhow::Result<String> {
+    let options = command.data.options();
+    let Some((name, sub_options)) = commands::subcommand(&options) else {
+        return Ok("Unknown subcommand.".to_string());
+    };
+    let state = match commands::option(sub_options, "state") {
+        Some(ResolvedValue::String(state)) => Some(*state == "on"),
+        _ => None,
+    };
+
+    if name == "me" {
+        let enabled = state.unwrap_or(true);
+        set_user(pool, command.user.id, enabled).await?;
+        return Ok(if enabled {
+            "🔗 The bot will repost fixed links from your messages again.".to_string()
+        } else {
+            "🔕 The bot will no longer repost links from your messages.".to_string()
+        });
+    }
+
+    let Some(guild_id) = command.guild_id else {
+        return Ok("This only works in a server.".to_string());
+    };
+
+    match name {
+        "enable" | "disable" => {
+            if !has_permission(command, Permissions::MANAGE_CHANNELS) {
+                return Ok("You need Manage Channels to change this.".to_string());
+            }
+            let channel_id = match commands::option(sub_options, "channel") {
+                Some(ResolvedValue::Channel(channel)) => channel.id,
+                _ => command.channel_id,
+            };
+            let enabled = name == "enable";
+            set_channel(pool, guild_id, channel_id, enabled).await?;
+            println!(
+                "[linkfix_settings] {} link reposts in channel {} (by {})",
+                if enabled { "Enabled" } else { "Disabled" },
+                channel_id,
+                command.user.name
+            );
+            Ok(format!(
+                "Link reposts are now **{}** in <#{}>.",
+                if enabled { "on" } else { "off" },
+                channel_id
+            ))
+        }
+        "server" => {
+            if !has_permission(command, Permissions::MANAGE_GUILD) {
+                return Ok("You need Manage Server to change this.".to_string());
+            }
+            let enabled = state.unwrap_or(true);
+            set_guild(pool, guild_id, enabled).await?;
+            println!(
+                "[linkfix_settings] {} link reposts in guild {} (by {})",
+                if enabled { "Enabled" } else { "Disabled" },
+                guild_id,
+                command.user.name
+            );
+            Ok(format!(
+                "Link reposts are now **{}** server-wide. Channel settings still take priority.",
+                if enabled { "on" } else { "off" }
+            ))
+        }
+        "duplicates" => {
+            if !has_permission(command, Permissions::MANAGE_GUILD) {
+                return Ok("You need Manage Server to change this.".to_string());
+            }
+            let mode = match commands::option(sub_options, "mode") {
+                Some(ResolvedValue::String(mode)) => DuplicateMode::parse(mode).unwrap_or(DuplicateMode::React),
+                _ => DuplicateMode::React,
+            };
+            let window_hours = match commands::option(sub_options, "window_hours") {
+                Some(ResolvedValue::Integer(hours)) => Some(*hours as i32),
+                _ => None,
+            };
+            set_duplicate_settings(pool, guild_id, mode, window_hours).await?;
+            let (mode, hours) = duplicate_settings(pool, guild_id).await?;
+            Ok(format!(
+                "Repeated links are now handled with **{}** (looking back {} hour(s)).",
+                mode.as_str(),
+                hours
+            ))
+        }
+        "status" => {
+            let here = is_enabled(pool, Some(guild_id), command.channel_id, command.user.id).await?;
+            let (duplicate_mode, duplicate_hours) = duplicate_settings(pool, guild_id).await?;
+            let disabled = disabled_channels(pool, guild_id).await?;
+            let disabled_list = if disabled.is_empty() {
+                "none".to_string()
+            } else {
+                disabled.iter().map(|c| format!("<#{}>", c)).collect::<Vec<_>>().join(", ")
+            };
+            Ok(format!(
+                "Link reposts for your messages in this channel: **{}**\nDisabled channels: {}\n\
+                 Repeated links: **{}** within {} hour(s)",
+                if here { "on" } else { "off" },
+                disabled_list,
+                duplicate_mode.as_str(),
+                duplicate_hours
+            ))
+        }
+        _ => Ok("Unknown subcommand.".to_string()),
+    }
+}
+
+pub async fn run_command(ctx: &Context, command: &CommandInteraction, pool: &PgPool) -> serenity::Result<()> {
+    let content = match handle(command, pool).await {
+        Ok(content) => content,
+        Err(e) => {
+            eprintln!("[linkfix_settings] Command error: {}", e);
+            "Something went wrong saving that setting.".to_string()
+        }
+    };
+    commands::reply_ephemeral(ctx, command, content).await
+}
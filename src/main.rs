@@ -1,161 +1,454 @@
 mod shop_watcher;
 mod game_notes_watcher;
+mod link_rewriter;
+mod link_replies;
+mod url_sanitizer;
+mod discord_markdown;
+mod mirror_health;
+mod commands;
+mod linkfix_settings;
+mod link_archive;
+mod duplicate_links;
+mod link_metadata;
+mod triggers;
+mod trigger_commands;
+mod speller;
+mod player_triggers;
+mod cooldown;
+mod emoji_registry;
+mod reaction_stats;
+mod reaction_policy;
+mod roster;
+mod roster_commands;
 
 use serenity::async_trait;
+use serenity::builder::{CreateAllowedMentions, CreateEmbed, CreateMessage, EditMessage};
+use serenity::model::application::{Command, Interaction};
 use serenity::model::channel::Message;
 use serenity::model::channel::Reaction;
 use serenity::model::gateway::Ready;
-use serenity::model::id::{EmojiId, ChannelId};
+use serenity::model::event::MessageUpdateEvent;
+use serenity::model::id::{EmojiId, ChannelId, GuildId, MessageId, UserId};
 use serenity::model::channel::ReactionType;
+use serenity::model::guild::{Emoji, Guild};
 use serenity::prelude::*;
-use rand::Rng;
 use dotenv::dotenv;
-use serde::Deserialize;
+use std::collections::HashMap;
 use std::sync::Arc;
 use futures_util::stream::{StreamExt};
 use tokio_postgres::{NoTls, Error as PgError, AsyncMessage};
+use link_rewriter::LinkRewriter;
+use url_sanitizer::UrlSanitizer;
+use mirror_health::MirrorHealth;
+use duplicate_links::DuplicateMode;
+use link_metadata::MetadataFetcher;
+use triggers::TriggerEngine;
+use player_triggers::PlayerTriggers;
+use cooldown::Cooldowns;
+use emoji_registry::{EmojiRef, EmojiRegistry};
+use reaction_stats::ReactionContext;
+use roster::RosterChangeEvent;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 
 
-// Emoji constants
-const EMOJI_AC: u64 = 1460415544229363944;
-const EMOJI_TI: u64 = 1460415567457554483;
-const EMOJI_VA: u64 = 1460415586399027200;
-const EMOJI_TE: u64 = 1460415609664835807;
-const EMOJI_D: u64 = 1460415630074449960;
-const EMOJI_MADI_KNIFE: u64 = 1421229718023442563;
-const EMOJI_AC_ALT: u64 = 1460433484337385514;
-
-// Convert x.com and twitter.com links to xcancel.com
-fn convert_twitter_links(text: &str) -> Option<String> {
-    let replaced = replace_twitter_links(text);
-    if replaced != text {
-        Some(replaced)
-    } else {
-        None
+// The reactions that complete :AC: into ACTIVATED
+const COMPLETION_EMOJIS: [EmojiRef; 4] = [emoji_registry::TI, emoji_registry::VA, emoji_registry::TE, emoji_registry::D];
+
+// Whether the bot has Manage Messages in a guild channel (or a thread's parent), going by
+// the cache. DMs and anything not cached count as no.
+fn can_manage_messages(ctx: &Context, guild_id: Option<GuildId>, channel_id: ChannelId) -> bool {
+    let Some(guild) = guild_id.and_then(|guild_id| ctx.cache.guild(guild_id)) else {
+        return false;
+    };
+    let channel_id = guild
+        .threads
+        .iter()
+        .find(|thread| thread.id == channel_id)
+        .and_then(|thread| thread.parent_id)
+        .unwrap_or(channel_id);
+    let bot_id = ctx.cache.current_user().id;
+    match (guild.channels.get(&channel_id), guild.members.get(&bot_id)) {
+        (Some(channel), Some(member)) => guild.user_permissions_in(channel, member).manage_messages(),
+        _ => false,
     }
 }
 
-fn replace_twitter_links(text: &str) -> String {
-    let mut result = String::new();
-    let mut remaining = text;
-    loop {
-        let lower = remaining.to_lowercase();
-        let pos = lower.find("https://x.com/")
-            .or_else(|| lower.find("http://x.com/"))
-            .or_else(|| lower.find("https://twitter.com/"))
-            .or_else(|| lower.find("http://twitter.com/"));
-        match pos {
-            None => {
-                result.push_str(remaining);
-                break;
+fn bot_reacted(msg: &Message, emoji: &EmojiRef) -> bool {
+    msg.reactions.iter().any(|r| r.me && emoji.matches(&r.reaction_type))
+}
+
+// Helper function to add emoji sequence to a message; returns the reactions that went on
+async fn add_emoji_sequence(msg: &Message, ctx: &Context, emojis: Vec<ReactionType>) -> Vec<ReactionType> {
+    let mut added = Vec::new();
+    for emoji in emojis {
+        if let Err(why) = msg.react(&ctx.http, emoji.clone()).await {
+            println!("Error adding reaction: {:?}", why);
+            break;
+        }
+        added.push(emoji);
+    }
+    added
+}
+
+struct Handler {
+    pool: sqlx::PgPool,
+    link_rewriter: LinkRewriter,
+    url_sanitizer: UrlSanitizer,
+    mirror_health: MirrorHealth,
+    metadata_fetcher: Box<dyn MetadataFetcher>,
+    triggers: TriggerEngine,
+    players: PlayerTriggers,
+    cooldowns: Cooldowns,
+    emojis: EmojiRegistry,
+    // Seeded from REACTION_RNG_SEED when set, so reaction picks can be replayed
+    rng: std::sync::Mutex<StdRng>,
+    // TRIGGER_EDIT_REMOVE_STALE: take reactions back when an edit stops a trigger matching
+    remove_stale_reactions: bool,
+}
+
+// Discord allows at most 10 embeds per message
+const MAX_EMBEDS: usize = 10;
+
+// Roster notifications this close together are posted as one digest
+const DEFAULT_ROSTER_DIGEST_WINDOW_SECS: u64 = 10;
+// ...but a steady trickle of them still goes out after this long
+const DEFAULT_ROSTER_DIGEST_MAX_WAIT_SECS: u64 = 60;
+
+// A link the bot will repost, and the source URL to build an embed from, if any
+struct ConvertedLink {
+    text: String,
+    unfurl: Option<String>,
+}
+
+impl Handler {
+    // Every link worth reposting: mirror rewrites, plus cleaned copies of other links that
+    // carried tracking parameters. In order of appearance, without repeats. Links in code or
+    // <suppressed> form are left alone; spoilered links stay spoilered.
+    fn converted_links(&self, content: &str) -> Vec<ConvertedLink> {
+        let mut links: Vec<ConvertedLink> = Vec::new();
+        for found in discord_markdown::find_links(content) {
+            let cleaned = self.url_sanitizer.clean(found.url);
+            let source = cleaned.as_deref().unwrap_or(found.url);
+            let (converted, unfurl) = match self.link_rewriter.rewrite_url(source) {
+                // Unfurling a spoilered link would give it away
+                Some((_, rewritten)) => (Some(rewritten), (!found.spoiler).then(|| source.to_string())),
+                None => (cleaned, None),
+            };
+            if let Some(text) = converted.map(|c| found.rewrap(&c)) {
+                if !links.iter().any(|link| link.text == text) {
+                    links.push(ConvertedLink { text, unfurl });
+                }
             }
-            Some(i) => {
-                result.push_str(&remaining[..i]);
-                let url_start = &remaining[i..];
-                let end = url_start.find(|c: char| c.is_whitespace()).unwrap_or(url_start.len());
-                let url = &url_start[..end];
-                let lower_url = url.to_lowercase();
-                let converted = if lower_url.starts_with("https://x.com/") || lower_url.starts_with("http://x.com/") {
-                    url.replacen("x.com", "xcancel.com", 1)
-                } else {
-                    url.replacen("twitter.com", "xcancel.com", 1)
-                };
-                result.push_str(&converted);
-                remaining = &url_start[end..];
+        }
+        links
+    }
+
+    // Reply body and embeds for the converted links. Links we could build an embed for are
+    // wrapped in <> so Discord doesn't add its own preview as well.
+    async fn render_links(&self, links: &[ConvertedLink]) -> (String, Vec<CreateEmbed>) {
+        // All previews are fetched together, so slow mirrors cost one timeout, not one each
+        let sources: Vec<&str> = links.iter().filter_map(|link| link.unfurl.as_deref()).take(MAX_EMBEDS).collect();
+        let mut fetched =
+            link_metadata::fetch_many(&*self.metadata_fetcher, &sources, link_metadata::FETCH_ALL_TIMEOUT)
+                .await
+                .into_iter();
+
+        let mut lines = Vec::new();
+        let mut embeds = Vec::new();
+        // Embeds that would push the message past Discord's total are dropped
+        let mut embed_len = 0;
+        for link in links {
+            let metadata = link.unfurl.as_ref().and_then(|_| fetched.next()).flatten();
+            match metadata {
+                Some(metadata) if embed_len + metadata.embed_len() <= link_metadata::EMBED_TOTAL_LIMIT => {
+                    embed_len += metadata.embed_len();
+                    lines.push(format!("<{}>", link.text));
+                    embeds.push(metadata.to_embed());
+                }
+                _ => lines.push(link.text.clone()),
             }
         }
+        (lines.join("\n"), embeds)
     }
-    result
-}
 
-// Convert instagram.com links to zzinstagram.com
-fn convert_instagram_links(text: &str) -> Option<String> {
-    let replaced = replace_instagram_links(text);
-    if replaced != text {
-        Some(replaced)
-    } else {
-        None
+    // Every link in the message with its canonical form, for the archive and duplicate checks
+    fn shared_links(&self, content: &str) -> Vec<link_archive::ArchivedLink> {
+        discord_markdown::find_links(content)
+            .into_iter()
+            .map(|found| {
+                let cleaned = self.url_sanitizer.clean(found.url);
+                link_archive::ArchivedLink {
+                    original_url: found.url.to_string(),
+                    canonical_url: self.link_rewriter.canonical_url(cleaned.as_deref().unwrap_or(found.url)),
+                }
+            })
+            .collect()
     }
-}
 
-fn replace_instagram_links(text: &str) -> String {
-    let mut result = String::new();
-    let mut remaining = text;
-    loop {
-        let lower = remaining.to_lowercase();
-        let pos = lower.find("https://www.instagram.com/")
-            .or_else(|| lower.find("http://www.instagram.com/"))
-            .or_else(|| lower.find("https://instagram.com/"))
-            .or_else(|| lower.find("http://instagram.com/"));
-        match pos {
-            None => {
-                result.push_str(remaining);
-                break;
+    // Point out links that were already shared in this guild recently. Runs before the
+    // message is archived so it doesn't match itself.
+    async fn flag_duplicate_links(&self, ctx: &Context, msg: &Message, links: &[link_archive::ArchivedLink]) {
+        let Some(guild_id) = msg.guild_id else {
+            return;
+        };
+        let (mode, window_hours) = match linkfix_settings::duplicate_settings(&self.pool, guild_id).await {
+            Ok(settings) => settings,
+            Err(e) => {
+                eprintln!("Error reading duplicate link settings: {:?}", e);
+                return;
             }
-            Some(i) => {
-                result.push_str(&remaining[..i]);
-                let url_start = &remaining[i..];
-                let end = url_start.find(|c: char| c.is_whitespace()).unwrap_or(url_start.len());
-                let url = &url_start[..end];
-                let converted = url.replacen("instagram.com", "zzinstagram.com", 1);
-                result.push_str(&converted);
-                remaining = &url_start[end..];
+        };
+        if mode == DuplicateMode::Off {
+            return;
+        }
+
+        for link in links {
+            let first = match duplicate_links::find_first_posting(
+                &self.pool,
+                guild_id,
+                &link.canonical_url,
+                window_hours,
+                msg.id,
+            )
+            .await
+            {
+                Ok(Some(first)) => first,
+                Ok(None) => continue,
+                Err(e) => {
+                    eprintln!("Error checking for duplicate links: {:?}", e);
+                    return;
+                }
+            };
+
+            println!("Detected repeated link {} (first posted in message {})", link.canonical_url, first.message_id);
+            let result = match mode {
+                DuplicateMode::Reply => {
+                    let reply = CreateMessage::new()
+                        .content(duplicate_links::format_reply(&first, guild_id))
+                        .reference_message(msg)
+                        .allowed_mentions(CreateAllowedMentions::new().replied_user(false));
+                    msg.channel_id.send_message(&ctx.http, reply).await.map(|_| ())
+                }
+                _ => msg.react(&ctx.http, duplicate_links::MARKER_EMOJI).await.map(|_| ()),
+            };
+            if let Err(why) = result {
+                println!("Error flagging repeated link: {:?}", why);
             }
+            // One flag per message is enough
+            return;
         }
     }
-    result
-}
 
-// Helper function to create custom emoji
-fn custom_emoji(id: u64, name: &str) -> ReactionType {
-    ReactionType::Custom {
-        animated: false,
-        id: EmojiId::new(id),
-        name: Some(name.to_string()),
+    // Keep the bot's rewritten-links reply in step with the source message: post it, edit it,
+    // or delete it when the links are gone
+    async fn sync_rewritten_links(
+        &self,
+        ctx: &Context,
+        guild_id: Option<GuildId>,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        author_id: UserId,
+        content: &str,
+    ) {
+        let links = self.converted_links(content);
+        let rendered = links.iter().map(|link| link.text.as_str()).collect::<Vec<_>>().join("\n");
+
+        let existing = match link_replies::find(&self.pool, message_id).await {
+            Ok(existing) => existing,
+            Err(e) => {
+                eprintln!("Error looking up rewritten-link reply: {:?}", e);
+                None
+            }
+        };
+
+        match existing {
+            None if links.is_empty() => {}
+            None => {
+                match linkfix_settings::is_enabled(&self.pool, guild_id, channel_id, author_id).await {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        println!("Link reposts are turned off here, skipping message {}", message_id);
+                        return;
+                    }
+                    Err(e) => eprintln!("Error checking linkfix settings: {:?}", e),
+                }
+
+                let (body, embeds) = self.render_links(&links).await;
+                let reply = |body: String, embeds: Vec<CreateEmbed>| {
+                    CreateMessage::new()
+                        .content(body)
+                        .embeds(embeds)
+                        .reference_message((channel_id, message_id))
+                        .allowed_mentions(CreateAllowedMentions::new().replied_user(false))
+                };
+                let sent = match channel_id.send_message(&ctx.http, reply(body, embeds)).await {
+                    Ok(sent) => sent,
+                    Err(why) => {
+                        // Fall back to plain links so the reply still goes out
+                        println!("Error sending rewritten links, retrying without embeds: {:?}", why);
+                        match channel_id.send_message(&ctx.http, reply(rendered.clone(), Vec::new())).await {
+                            Ok(sent) => sent,
+                            Err(why) => {
+                                println!("Error sending rewritten links: {:?}", why);
+                                return;
+                            }
+                        }
+                    }
+                };
+
+                // Needs Manage Messages; without it the original embeds just stay
+                if can_manage_messages(ctx, guild_id, channel_id) {
+                    let suppress = EditMessage::new().suppress_embeds(true);
+                    if let Err(why) = channel_id.edit_message(&ctx.http, message_id, suppress).await {
+                        println!("Could not suppress embeds on original message: {:?}", why);
+                    }
+                }
+
+                if let Err(e) = link_replies::record(&self.pool, message_id, channel_id, sent.id, &rendered).await {
+                    eprintln!("Error recording rewritten-link reply: {:?}", e);
+                }
+            }
+            Some(tracked) if links.is_empty() => {
+                println!("Source message {} no longer has rewritable links, deleting reply", message_id);
+                if let Err(why) = tracked.channel_id.delete_message(&ctx.http, tracked.reply_id).await {
+                    println!("Error deleting rewritten-link reply: {:?}", why);
+                }
+                if let Err(e) = link_replies::forget(&self.pool, message_id).await {
+                    eprintln!("Error forgetting rewritten-link reply: {:?}", e);
+                }
+            }
+            Some(tracked) if tracked.content == rendered => {}
+            Some(tracked) => {
+                println!("Source message {} was edited, updating reply", message_id);
+                let (body, embeds) = self.render_links(&links).await;
+                let edit = EditMessage::new().content(body).embeds(embeds);
+                if let Err(why) = tracked.channel_id.edit_message(&ctx.http, tracked.reply_id, edit).await {
+                    println!("Error editing rewritten-link reply, retrying without embeds: {:?}", why);
+                    let plain = EditMessage::new().content(rendered.clone()).embeds(Vec::new());
+                    if let Err(why) = tracked.channel_id.edit_message(&ctx.http, tracked.reply_id, plain).await {
+                        println!("Error editing rewritten-link reply: {:?}", why);
+                        return;
+                    }
+                }
+                if let Err(e) = link_replies::update_content(&self.pool, message_id, &rendered).await {
+                    eprintln!("Error updating rewritten-link reply: {:?}", e);
+                }
+            }
+        }
     }
-}
 
-// Helper function to get ACTIVATED sequence
-fn activated_sequence() -> Vec<ReactionType> {
-    vec![
-        custom_emoji(EMOJI_AC, "AC"),
-        custom_emoji(EMOJI_TI, "TI"),
-        custom_emoji(EMOJI_VA, "VA"),
-        custom_emoji(EMOJI_TE, "TE"),
-        custom_emoji(EMOJI_D, "D_"),
-    ]
-}
+    /// Fire reaction triggers ("activated", "madi", ...) for a message, highest priority
+    /// first, then players. On an edit, rules that already reacted keep their reactions and
+    /// only newly matching rules fire.
+    async fn run_triggers(&self, ctx: &Context, msg: &Message, edited: bool) {
+        let mut fired = self.triggers.matching(&msg.content, msg.guild_id);
+        self.players.extend_fired(&mut fired, &msg.content);
 
-// Helper function to get ACTIVATE sequence (no D)
-fn activate_sequence() -> Vec<ReactionType> {
-    vec![
-        custom_emoji(EMOJI_AC, "AC"),
-        custom_emoji(EMOJI_TI, "TI"),
-        custom_emoji(EMOJI_VA, "VA"),
-        custom_emoji(EMOJI_TE, "TE"),
-    ]
-}
+        if edited {
+            let previous = match reaction_stats::fired_on(&self.pool, msg.id).await {
+                Ok(previous) => previous,
+                Err(e) => {
+                    eprintln!("Error reading earlier trigger reactions: {:?}", e);
+                    return;
+                }
+            };
+            if self.remove_stale_reactions {
+                self.remove_stale_reactions(ctx, msg, &fired, &previous).await;
+            }
+            fired.retain(|rule| !previous.iter().any(|(trigger, _)| trigger == &rule.name));
+        }
 
-// Helper function to add emoji sequence to a message
-async fn add_emoji_sequence(msg: &Message, ctx: &Context, emojis: Vec<ReactionType>) {
-    for emoji in emojis {
-        if let Err(why) = msg.react(&ctx.http, emoji).await {
-            println!("Error adding reaction: {:?}", why);
-            break;
+        fired.retain(|rule| self.cooldowns.allow(&rule.name, msg.channel_id, msg.author.id));
+        if fired.is_empty() {
+            return;
+        }
+        let names: Vec<&str> = fired.iter().map(|rule| rule.name.as_str()).collect();
+        println!("Detected triggers {:?}{}", names, if edited { " after edit" } else { "" });
+        let mut planned = TriggerEngine::plan(&fired, &mut *self.rng.lock().unwrap());
+        if edited {
+            // Replies aren't recorded like reactions, so there's no telling whether one was
+            // already sent; never send it again for an edit
+            planned.retain(|(_, action)| !matches!(action, triggers::PlannedAction::Reply(_)));
         }
+        let added = triggers::execute(ctx, msg, &self.emojis, planned).await;
+        let context = ReactionContext {
+            guild_id: msg.guild_id,
+            channel_id: msg.channel_id,
+            message_id: msg.id,
+            user_id: msg.author.id,
+        };
+        reaction_stats::record_logged(&self.pool, context, &added).await;
     }
-}
 
-struct Handler;
+    /// Take back reactions from rules that matched before an edit but no longer do.
+    /// Emoji another still-matching rule added stay.
+    async fn remove_stale_reactions(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        fired: &[triggers::TriggerRule],
+        previous: &[(String, String)],
+    ) {
+        let still_fires = |trigger: &str| {
+            // Reactions from /spell and :AC: completions don't come from message content
+            trigger == "spell" || trigger == "ac_completion" || fired.iter().any(|rule| rule.name == trigger)
+        };
+        let mut stale: Vec<String> = previous
+            .iter()
+            .filter(|(trigger, _)| !still_fires(trigger))
+            .map(|(trigger, _)| trigger.clone())
+            .collect();
+        stale.dedup();
+        if stale.is_empty() {
+            return;
+        }
+
+        let kept: Vec<&str> = previous
+            .iter()
+            .filter(|(trigger, _)| still_fires(trigger))
+            .map(|(_, emoji)| emoji.as_str())
+            .collect();
+        let remove: Vec<ReactionType> = msg
+            .reactions
+            .iter()
+            .filter(|r| r.me)
+            .map(|r| r.reaction_type.clone())
+            .filter(|emoji| {
+                let emoji = emoji.to_string();
+                !kept.contains(&emoji.as_str())
+                    && previous.iter().any(|(trigger, e)| *e == emoji && stale.contains(trigger))
+            })
+            .collect();
 
-#[derive(Debug, Deserialize)]
-#[allow(dead_code)] 
-struct RosterChangeEvent {
-    event_type: String,
-    number: i32,
-    name: String,
-    ao_datetime: String,
-    event_time: String,
+        println!("Triggers {:?} no longer match after edit, removing {} reaction(s)", stale, remove.len());
+        for emoji in remove {
+            if let Err(why) = msg.delete_reaction(&ctx.http, None, emoji).await {
+                println!("Error removing reaction: {:?}", why);
+                break;
+            }
+        }
+        if let Err(e) = reaction_stats::forget(&self.pool, msg.id, &stale).await {
+            eprintln!("Error forgetting stale trigger reactions: {:?}", e);
+        }
+    }
+
+    // Remove the bot's reply when its source message is deleted
+    async fn drop_rewritten_links(&self, ctx: &Context, message_id: MessageId) {
+        match link_replies::find(&self.pool, message_id).await {
+            Ok(Some(tracked)) => {
+                println!("Source message {} was deleted, deleting reply", message_id);
+                if let Err(why) = tracked.channel_id.delete_message(&ctx.http, tracked.reply_id).await {
+                    println!("Error deleting rewritten-link reply: {:?}", why);
+                }
+                if let Err(e) = link_replies::forget(&self.pool, message_id).await {
+                    eprintln!("Error forgetting rewritten-link reply: {:?}", e);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("Error looking up rewritten-link reply: {:?}", e),
+        }
+    }
 }
 
 #[async_trait]
@@ -169,47 +462,122 @@ impl EventHandler for Handler {
         let content_lower = msg.content.to_lowercase();
         println!("Received message: {}", content_lower);
 
-        // Convert twitter/x links to xcancel
-        if let Some(converted) = convert_twitter_links(&msg.content) {
-            if let Err(why) = msg.channel_id.say(&ctx.http, converted).await {
-                println!("Error sending xcancel link: {:?}", why);
-            }
-        }
+        // Rewrite links (x.com -> xcancel.com, instagram.com -> zzinstagram.com, ...)
+        self.sync_rewritten_links(&ctx, msg.guild_id, msg.channel_id, msg.id, msg.author.id, &msg.content)
+            .await;
 
-        // Convert instagram.com links to zzinstagram.com
-        if let Some(converted) = convert_instagram_links(&msg.content) {
-            if let Err(why) = msg.channel_id.say(&ctx.http, converted).await {
-                println!("Error sending zzinstagram link: {:?}", why);
+        let shared = self.shared_links(&msg.content);
+        if !shared.is_empty() {
+            self.flag_duplicate_links(&ctx, &msg, &shared).await;
+            if let Err(e) = link_archive::record(&self.pool, &msg, &shared).await {
+                eprintln!("Error archiving shared links: {:?}", e);
             }
         }
 
-        // Check for "activated" (full word)
-        if content_lower.contains("activated") {
-            println!("Detected 'activated', adding full ACTIVATED sequence");
-            add_emoji_sequence(&msg, &ctx, activated_sequence()).await;
+        self.run_triggers(&ctx, &msg, false).await;
+    }
+
+    async fn message_update(
+        &self,
+        ctx: Context,
+        _old_if_available: Option<Message>,
+        _new: Option<Message>,
+        event: MessageUpdateEvent,
+    ) {
+        let Some(author) = &event.author else {
             return;
-        }
-        
-        // Check for "activate" (without the d)
-        if content_lower.contains("activate") {
-            println!("Detected 'activate', adding ACTIVATE sequence (no D)");
-            add_emoji_sequence(&msg, &ctx, activate_sequence()).await;
+        };
+        if author.bot {
             return;
         }
-        
-        // Check for "madi" mention
-        let has_madi_word = content_lower.split_whitespace()
-            .any(|word| word.trim_matches(|c: char| !c.is_alphabetic()) == "madi");
-        let has_madi_parsons = content_lower.contains("madi parsons");
-        
-        if has_madi_word || has_madi_parsons {
-            println!("Detected madi mention, adding random reactions");
-            handle_madi_reactions(&msg, &ctx).await;
+        // Embed-only updates carry no content; nothing to resync
+        if let Some(content) = &event.content {
+            self.sync_rewritten_links(&ctx, event.guild_id, event.channel_id, event.id, author.id, content)
+                .await;
+
+            match event.channel_id.message(&ctx.http, event.id).await {
+                Ok(mut msg) => {
+                    // Fetched messages don't carry the guild id
+                    msg.guild_id = event.guild_id;
+                    self.run_triggers(&ctx, &msg, true).await;
+                }
+                Err(why) => println!("Error fetching edited message: {:?}", why),
+            }
         }
     }
 
-    async fn ready(&self, _: Context, ready: Ready) {
+    async fn message_delete(
+        &self,
+        ctx: Context,
+        _channel_id: ChannelId,
+        deleted_message_id: MessageId,
+        _guild_id: Option<GuildId>,
+    ) {
+        self.drop_rewritten_links(&ctx, deleted_message_id).await;
+    }
+
+    async fn message_delete_bulk(
+        &self,
+        ctx: Context,
+        _channel_id: ChannelId,
+        multiple_deleted_messages_ids: Vec<MessageId>,
+        _guild_id: Option<GuildId>,
+    ) {
+        for message_id in multiple_deleted_messages_ids {
+            self.drop_rewritten_links(&ctx, message_id).await;
+        }
+    }
+
+    async fn ready(&self, ctx: Context, ready: Ready) {
         println!("{} is connected!", ready.user.name);
+        self.emojis.load_from_cache(&ctx);
+
+        match Command::set_global_commands(&ctx.http, commands::all()).await {
+            Ok(registered) => println!("Registered {} slash command(s)", registered.len()),
+            Err(why) => println!("Error registering slash commands: {:?}", why),
+        }
+    }
+
+    async fn guild_create(&self, _ctx: Context, guild: Guild, _is_new: Option<bool>) {
+        self.emojis.load_guild(guild.id, guild.emojis.values());
+    }
+
+    async fn guild_emojis_update(&self, _ctx: Context, guild_id: GuildId, current_state: HashMap<EmojiId, Emoji>) {
+        self.emojis.load_guild(guild_id, current_state.values());
+    }
+
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        let command = match interaction {
+            Interaction::Command(command) => command,
+            Interaction::Autocomplete(autocomplete) => {
+                if autocomplete.data.name == "player" {
+                    if let Err(why) = roster_commands::autocomplete(&ctx, &autocomplete, &self.pool).await {
+                        println!("Error answering /player autocomplete: {:?}", why);
+                    }
+                }
+                return;
+            }
+            _ => return,
+        };
+
+        println!("Slash command /{} from {}", command.data.name, command.user.name);
+        let result = match command.data.name.as_str() {
+            "mirrors" => {
+                mirror_health::run_command(&ctx, &command, &self.mirror_health, &self.link_rewriter).await
+            }
+            "linkfix" => linkfix_settings::run_command(&ctx, &command, &self.pool).await,
+            "links" => link_archive::run_command(&ctx, &command, &self.pool).await,
+            "trigger" => {
+                trigger_commands::run_command(&ctx, &command, &self.pool, &self.triggers, &self.players).await
+            }
+            "spell" => speller::run_command(&ctx, &command, &self.pool).await,
+            "stats" => reaction_stats::run_command(&ctx, &command, &self.pool).await,
+            "roster" | "player" => roster_commands::run_command(&ctx, &command, &self.pool).await,
+            _ => Ok(()),
+        };
+        if let Err(why) = result {
+            println!("Error handling /{}: {:?}", command.data.name, why);
+        }
     }
 
     async fn reaction_add(&self, ctx: Context, reaction: Reaction) {
@@ -225,78 +593,97 @@ impl EventHandler for Handler {
         println!("Reaction detected: {:?}", reaction.emoji);
         
         // Check if the reaction is :AC: emoji
-        if let ReactionType::Custom { id, .. } = &reaction.emoji {
-            if id.get() == EMOJI_AC || id.get() == EMOJI_AC_ALT {
-                println!("Detected :AC: reaction, completing ACTIVATED sequence");
-                
-                if let Ok(msg) = reaction.message(&ctx.http).await {
-                    // Add remaining emojis (skip AC since it's already there)
-                    let remaining = vec![
-                        custom_emoji(EMOJI_TI, "TI"),
-                        custom_emoji(EMOJI_VA, "VA"),
-                        custom_emoji(EMOJI_TE, "TE"),
-                        custom_emoji(EMOJI_D, "D_"),
-                    ];
-                    add_emoji_sequence(&msg, &ctx, remaining).await;
-                } else {
-                    println!("Error fetching message");
-                }
-            }
+        if !emoji_registry::AC.matches(&reaction.emoji) {
+            return;
+        }
+        let Ok(msg) = reaction.message(&ctx.http).await else {
+            println!("Error fetching message");
+            return;
+        };
+
+        // Add remaining emojis (skip AC since it's already there), minus any the bot already added
+        let remaining: Vec<ReactionType> = COMPLETION_EMOJIS
+            .iter()
+            .filter(|emoji| !bot_reacted(&msg, emoji))
+            .filter_map(|emoji| self.emojis.resolve_ref(reaction.guild_id, emoji))
+            .collect();
+        if remaining.is_empty() {
+            println!("ACTIVATED sequence already complete");
+            return;
+        }
+
+        let throttled = reaction
+            .user_id
+            .is_some_and(|user_id| !self.cooldowns.allow("ac_completion", reaction.channel_id, user_id));
+        if throttled {
+            return;
+        }
+        println!("Detected :AC: reaction, completing ACTIVATED sequence");
+        let added = add_emoji_sequence(&msg, &ctx, remaining).await;
+        if let Some(user_id) = reaction.user_id {
+            let context = ReactionContext {
+                guild_id: reaction.guild_id,
+                channel_id: reaction.channel_id,
+                message_id: reaction.message_id,
+                user_id,
+            };
+            let added: Vec<(String, ReactionType)> =
+                added.into_iter().map(|emoji| ("ac_completion".to_string(), emoji)).collect();
+            reaction_stats::record_logged(&self.pool, context, &added).await;
         }
     }
-}
 
-// Handle madi-specific reactions
-async fn handle_madi_reactions(msg: &Message, ctx: &Context) {
-// Change this line to:
-let (selected_unicode, use_custom, use_activated) = {
-            // All RNG work happens in this temporary block
-        let mut rng = rand::thread_rng();
-        
-        let reactions_unicode: Vec<Vec<char>> = vec![
-            vec!['🥵'], vec!['😍'], vec!['💖'], vec!['🥹'],
-            vec!['🤤'], vec!['😋'], vec!['🤠'], vec!['💪'],
-            vec!['🇲', '🇦', '🇩', '🇮'], vec!['🇾', '🇪', '🇸'],
-        ];
-
-        let num_reactions = rng.gen_range(1..=3);
-        let mut selected = Vec::new();
-        let mut used_indices = Vec::new();
-        
-        while selected.len() < num_reactions {
-            let idx = rng.gen_range(0..reactions_unicode.len());
-            if !used_indices.contains(&idx) {
-                used_indices.push(idx);
-                selected.push(reactions_unicode[idx].clone());
+    async fn reaction_remove(&self, ctx: Context, reaction: Reaction) {
+        // Ignore the bot taking back its own reactions
+        if let Some(user_id) = reaction.user_id {
+            if let Ok(user) = user_id.to_user(&ctx.http).await {
+                if user.bot {
+                    return;
+                }
             }
         }
-        
-        let custom = rng.gen_range(0..100) < 20;
-        let activated = rng.gen_range(0..100) < 10;
-        
-        (selected, custom, activated)
-    }; // rng is dropped here, so it doesn't break the 'Send' requirement for the .await below
 
-    // Now it is safe to use .await
-for reaction_set in selected_unicode {
-        for emoji in reaction_set {
-            let _ = msg.react(&ctx.http, emoji).await;
+        if !emoji_registry::AC.matches(&reaction.emoji) {
+            return;
         }
-    }
+        let Ok(msg) = reaction.message(&ctx.http).await else {
+            println!("Error fetching message");
+            return;
+        };
 
+        // Someone still has :AC: up, or the bot reacted :AC: itself (an "activated" trigger):
+        // the completion still belongs
+        let humans: u64 = msg
+            .reactions
+            .iter()
+            .filter(|r| emoji_registry::AC.matches(&r.reaction_type))
+            .map(|r| r.count - u64::from(r.me))
+            .sum();
+        if humans > 0 || bot_reacted(&msg, &emoji_registry::AC) {
+            return;
+        }
 
-// Use the custom flag
-    if use_custom && !use_activated {
-        let _ = msg.react(&ctx.http, custom_emoji(EMOJI_MADI_KNIFE, "madi_knife")).await;
-    }
-    
-    // Use the activated flag
-    if use_activated {
-        add_emoji_sequence(msg, ctx, activated_sequence()).await;
+        println!("Last :AC: reaction removed, undoing ACTIVATED completion");
+        let added: Vec<ReactionType> = msg
+            .reactions
+            .iter()
+            .filter(|r| r.me && COMPLETION_EMOJIS.iter().any(|emoji| emoji.matches(&r.reaction_type)))
+            .map(|r| r.reaction_type.clone())
+            .collect();
+        for emoji in added {
+            if let Err(why) = msg.delete_reaction(&ctx.http, None, emoji).await {
+                println!("Error removing reaction: {:?}", why);
+                break;
+            }
+        }
     }
 }
 
-async fn listen_for_roster_changes(http: Arc<serenity::http::Http>) -> Result<(), PgError> {
+async fn listen_for_roster_changes(
+    http: Arc<serenity::http::Http>,
+    players: PlayerTriggers,
+    pool: sqlx::PgPool,
+) -> Result<(), PgError> {
     // 1. Fetch environment variables at the start to ensure they are in scope
     let db_host = std::env::var("DB_HOST").unwrap_or_else(|_| "192.168.2.66".to_string());
     let db_port = std::env::var("DB_PORT").unwrap_or_else(|_| "5432".to_string());
@@ -341,59 +728,186 @@ async fn listen_for_roster_changes(http: Arc<serenity::http::Http>) -> Result<()
     // 4. Start Listening
    client.execute("LISTEN roster_changes", &[]).await?;    
     println!(">>> System active: Waiting for roster_changes notifications...");
+
+    let channel = ChannelId::new(roster_channel_id);
+    let digest_window = tokio::time::Duration::from_secs(
+        std::env::var("ROSTER_DIGEST_WINDOW_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_ROSTER_DIGEST_WINDOW_SECS),
+    );
+    let digest_max_wait = tokio::time::Duration::from_secs(
+        std::env::var("ROSTER_DIGEST_MAX_WAIT_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_ROSTER_DIGEST_MAX_WAIT_SECS),
+    );
+
+    // Anything logged while we were down or reconnecting. LISTEN is already active, so
+    // nothing can slip in between the replay and the first notification.
+    replay_roster_log(&http, channel, &players, &pool).await;
     
     while let Some(notification) = rx.recv().await {
-        // 1. Get the JSON payload from the notification
-        let payload = notification.payload();
-        
-        // 2. Try to parse the JSON into our RosterChangeEvent struct
-        match serde_json::from_str::<RosterChangeEvent>(payload) {
-            Ok(event) => {
-                println!(">>> [DATABASE EVENT] New change detected via payload!");
-                println!(">>> [ROSTER CHANGE] Type: {}, Name: {}, Number: {}", 
-                         event.event_type, event.name, event.number);
-                
-                let message = format_roster_change_message(&event);
-                
-                // LOG: Show exactly what is being sent
-                println!(">>> [DISCORD PREVIEW]\n---\n{}\n---", message);
-
-                let channel = ChannelId::new(roster_channel_id);
-                if let Err(why) = channel.say(&http, &message).await {
-                    eprintln!(">>> [ERROR] Failed to send to Discord: {:?}", why);
-                } else {
-                    println!(">>> [SUCCESS] Message posted for: {}", event.name);
+        // 1. Gather everything that arrives within the window, so a bulk registration
+        //    posts one digest instead of a message per player
+        let mut payloads = vec![notification.payload().to_string()];
+        if !digest_window.is_zero() {
+            // Each arrival restarts the window, up to a digest's worth or the max wait
+            let give_up = tokio::time::Instant::now() + digest_max_wait;
+            while payloads.len() < roster::DIGEST_MAX_EVENTS {
+                let deadline = (tokio::time::Instant::now() + digest_window).min(give_up);
+                match tokio::time::timeout_at(deadline, rx.recv()).await {
+                    Ok(Some(notification)) => payloads.push(notification.payload().to_string()),
+                    _ => break,
                 }
-            },
-            Err(e) => {
-                eprintln!(">>> [ERROR] Failed to parse notification payload: {}", e);
-                // Fallback: If JSON parsing fails, you could keep your old SELECT LIMIT 1 logic here
             }
         }
+
+        // Logged changes are posted from roster_change_log, in order, past the cursor
+        let mut logged = false;
+        let mut events = Vec::new();
+        for payload in &payloads {
+            if roster::is_logged(payload) {
+                logged = true;
+                continue;
+            }
+            // 2. Try to parse the JSON into our RosterChangeEvent struct
+            match RosterChangeEvent::parse(payload) {
+                Ok(event) => {
+                    println!(">>> [DATABASE EVENT] New change detected via payload!");
+                    events.push(event);
+                }
+                Err(e) => {
+                    eprintln!(">>> [ERROR] Failed to parse notification payload: {}\n>>> [RAW PAYLOAD] {}", e, payload);
+                }
+            }
+        }
+
+        if logged {
+            replay_roster_log(&http, channel, &players, &pool).await;
+        }
+        if !events.is_empty() {
+            for chunk in events.chunks(roster::DIGEST_MAX_EVENTS) {
+                post_roster_changes(&http, channel, &pool, chunk).await;
+            }
+            reload_player_triggers(&players, &pool).await;
+        }
     }
 
     Ok(())
 }
 
-fn format_roster_change_message(event: &RosterChangeEvent) -> String {
-    let emoji = match event.event_type.as_str() {
-        "added" => "✅",
-        "removed" => "❌",
-        _ => "ℹ️",
-    };
+/// Post one roster change. Returns whether Discord accepted it.
+async fn announce_roster_change(
+    http: &serenity::http::Http,
+    channel: ChannelId,
+    pool: &sqlx::PgPool,
+    event: &RosterChangeEvent,
+) -> bool {
+    println!(">>> [ROSTER CHANGE] Type: {}, Name: {}, Number: {}", 
+             event.kind.event_type(), event.name, event.number);
     
-    let action = match event.event_type.as_str() {
-        "added" => "**ADDED TO ROSTER**",
-        "removed" => "**REMOVED FROM ROSTER**",
-        _ => "ROSTER CHANGE",
-    };
+    let message = roster::format_roster_change_message(event);
     
-    format!(
-        "{} {} {}\n**#{}** - {}",
-        emoji, action, emoji,
-        event.number, event.name
-    )
+    // LOG: Show exactly what is being sent
+    println!(">>> [DISCORD PREVIEW]\n---\n{}\n---", message);
+
+    // Details for the embed; without them it carries the plain text
+    let player = match roster::find(pool, &event.name, Some(event.number)).await {
+        Ok(player) => player,
+        Err(e) => {
+            eprintln!(">>> [ERROR] Failed to look up roster details: {}", e);
+            None
+        }
+    };
+    let announcement = CreateMessage::new().embed(roster::announcement_embed(event, player.as_ref()));
+
+    if let Err(why) = channel.send_message(http, announcement).await {
+        eprintln!(">>> [ERROR] Failed to send to Discord: {:?}", why);
+        false
+    } else {
+        println!(">>> [SUCCESS] Message posted for: {}", event.name);
+        true
+    }
+}
+
+/// One change posts on its own; several go out as a digest. Returns whether Discord
+/// accepted it.
+async fn post_roster_changes(
+    http: &serenity::http::Http,
+    channel: ChannelId,
+    pool: &sqlx::PgPool,
+    events: &[RosterChangeEvent],
+) -> bool {
+    match events {
+        [] => true,
+        [event] => announce_roster_change(http, channel, pool, event).await,
+        _ => {
+            println!(">>> [ROSTER DIGEST] {} changes", events.len());
+            println!(">>> [DISCORD PREVIEW]\n---\n{}\n---", roster::format_roster_digest(events));
+            let digest = CreateMessage::new().embed(roster::digest_embed(events));
+            if let Err(why) = channel.send_message(http, digest).await {
+                eprintln!(">>> [ERROR] Failed to send digest to Discord: {:?}", why);
+                false
+            } else {
+                println!(">>> [SUCCESS] Digest posted for {} changes", events.len());
+                true
+            }
+        }
+    }
+}
+
+/// Keep player mention triggers in step with the roster
+async fn reload_player_triggers(players: &PlayerTriggers, pool: &sqlx::PgPool) {
+    match players.reload(pool).await {
+        Ok(count) => println!(">>> [ROSTER] Player triggers reloaded: {} player(s)", count),
+        Err(e) => eprintln!(">>> [ERROR] Failed to reload player triggers: {}", e),
+    }
 }
+
+/// Post every logged change past the cursor, oldest first, as digests of up to
+/// `roster::DIGEST_MAX_EVENTS`. The cursor moves past a chunk only once its message is
+/// posted, so a failed post stops the replay and the next one retries the whole chunk in
+/// order (at least once per chunk, not exactly once).
+async fn replay_roster_log(
+    http: &serenity::http::Http,
+    channel: ChannelId,
+    players: &PlayerTriggers,
+    pool: &sqlx::PgPool,
+) {
+    let pending = match roster::pending_changes(pool).await {
+        Ok(pending) => pending,
+        Err(e) => {
+            eprintln!(">>> [ERROR] Failed to read roster_change_log: {}", e);
+            return;
+        }
+    };
+    if pending.is_empty() {
+        return;
+    }
+    println!(">>> [ROSTER] {} logged change(s) to post", pending.len());
+
+    for chunk in pending.chunks(roster::DIGEST_MAX_EVENTS) {
+        let mut events = Vec::new();
+        for (id, payload) in chunk {
+            match RosterChangeEvent::parse(payload) {
+                Ok(event) => events.push(event),
+                // Skipped for good; retrying can't fix the payload
+                Err(e) => eprintln!(">>> [ERROR] Failed to parse roster_change_log #{}: {}\n>>> [RAW PAYLOAD] {}", id, e, payload),
+            }
+        }
+        if !post_roster_changes(http, channel, pool, &events).await {
+            break;
+        }
+        let (last_id, _) = chunk[chunk.len() - 1];
+        if let Err(e) = roster::advance_cursor(pool, last_id).await {
+            eprintln!(">>> [ERROR] Failed to advance roster cursor to #{}: {}", last_id, e);
+            break;
+        }
+    }
+    reload_player_triggers(players, pool).await;
+}
+
 // add survey_info shit. scrape with rust instead of R.
 
 pub async fn run_survey_watcher(http: Arc<serenity::http::Http>) {
@@ -523,14 +1037,72 @@ async fn main() {
         .expect("Expected a token in the environment variable DISCORD_TOKEN");
 
     // Set gateway intents, which decides what events the bot will be notified about
-    let intents = GatewayIntents::GUILD_MESSAGES 
+    let intents = GatewayIntents::GUILDS
+        | GatewayIntents::GUILD_EMOJIS_AND_STICKERS
+        | GatewayIntents::GUILD_MESSAGES
         | GatewayIntents::DIRECT_MESSAGES
         | GatewayIntents::MESSAGE_CONTENT
         | GatewayIntents::GUILD_MESSAGE_REACTIONS;
 
+    let db_url = {
+        let host = std::env::var("DB_HOST").unwrap_or_else(|_| "192.168.2.66".to_string());
+        let port = std::env::var("DB_PORT").unwrap_or_else(|_| "5432".to_string());
+        let name = std::env::var("DB_NAME").unwrap_or_else(|_| "tb_sun".to_string());
+        let user = std::env::var("DB_USERNAME").expect("DB_USERNAME must be set");
+        let password = std::env::var("DB_PASSWORD").expect("DB_PASSWORD must be set");
+        format!("postgres://{}:{}@{}:{}/{}", user, password, host, port, name)
+    };
+
+    let pool = sqlx::PgPool::connect(&db_url)
+        .await
+        .expect("Failed to connect to Postgres");
+
+    if let Err(e) = link_replies::ensure_table(&pool).await {
+        eprintln!("Failed to create link_rewrite_replies table: {}", e);
+    }
+    if let Err(e) = linkfix_settings::ensure_tables(&pool).await {
+        eprintln!("Failed to create linkfix settings tables: {}", e);
+    }
+    if let Err(e) = link_archive::ensure_table(&pool).await {
+        eprintln!("Failed to create shared_links table: {}", e);
+    }
+    if let Err(e) = reaction_stats::ensure_tables(&pool).await {
+        eprintln!("Failed to create reaction stats tables: {}", e);
+    }
+    if let Err(e) = roster::ensure_tables(&pool).await {
+        eprintln!("Failed to create roster change log tables: {}", e);
+    }
+
+    let trigger_engine = TriggerEngine::default();
+    tokio::spawn(triggers::run(trigger_engine.clone(), pool.clone()));
+
+    let player_triggers = PlayerTriggers::default();
+    tokio::spawn(player_triggers::run(player_triggers.clone(), pool.clone()));
+
+    let mirror_health = MirrorHealth::default();
+    let link_rewriter = link_rewriter::load(&pool, mirror_health.clone()).await;
+    tokio::spawn(mirror_health::run(mirror_health.clone(), link_rewriter.mirror_hosts()));
+
+    let handler = Handler {
+        pool: pool.clone(),
+        link_rewriter,
+        url_sanitizer: url_sanitizer::load(&pool).await,
+        mirror_health,
+        metadata_fetcher: link_metadata::from_env(),
+        triggers: trigger_engine.clone(),
+        players: player_triggers.clone(),
+        cooldowns: Cooldowns::from_env(),
+        emojis: EmojiRegistry::default(),
+        rng: std::sync::Mutex::new(match std::env::var("REACTION_RNG_SEED").ok().and_then(|v| v.parse().ok()) {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        }),
+        remove_stale_reactions: std::env::var("TRIGGER_EDIT_REMOVE_STALE").is_ok_and(|v| v == "true" || v == "1"),
+    };
+
     // Create a new instance of the Client, logging in as a bot
     let mut client = Client::builder(&token, intents)
-        .event_handler(Handler)
+        .event_handler(handler)
         .await
         .expect("Error creating client");
 
@@ -538,10 +1110,11 @@ async fn main() {
     let http = client.http.clone();
 
     let http_survey = http.clone();
+    let roster_pool = pool.clone();
     // Spawn roster change listener in background
     tokio::spawn(async move {
         loop {
-            if let Err(e) = listen_for_roster_changes(http.clone()).await {
+            if let Err(e) = listen_for_roster_changes(http.clone(), player_triggers.clone(), roster_pool.clone()).await {
                 eprintln!("Roster change listener error: {:?}", e);
                 eprintln!("Reconnecting in 5 seconds...");
                 tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
@@ -551,26 +1124,15 @@ async fn main() {
 
     tokio::spawn(run_survey_watcher(http_survey));
 
+    tokio::spawn(reaction_stats::run(pool.clone(), Arc::clone(&client.http)));
+
     let shop_channel_id: u64 = std::env::var("SHOP_CHANNEL_ID")
         .expect("SHOP_CHANNEL_ID not set")
         .parse()
         .expect("SHOP_CHANNEL_ID must be a valid u64");
 
-    let db_url = {
-        let host = std::env::var("DB_HOST").unwrap_or_else(|_| "192.168.2.66".to_string());
-        let port = std::env::var("DB_PORT").unwrap_or_else(|_| "5432".to_string());
-        let name = std::env::var("DB_NAME").unwrap_or_else(|_| "tb_sun".to_string());
-        let user = std::env::var("DB_USERNAME").expect("DB_USERNAME must be set");
-        let password = std::env::var("DB_PASSWORD").expect("DB_PASSWORD must be set");
-        format!("postgres://{}:{}@{}:{}/{}", user, password, host, port, name)
-    };
-
-    let shop_pool = sqlx::PgPool::connect(&db_url)
-        .await
-        .expect("Failed to connect to Postgres for shop watcher");
-
     tokio::spawn(shop_watcher::run(
-        shop_pool.clone(),
+        pool.clone(),
         Arc::clone(&client.http),
         shop_channel_id,
     ));
@@ -581,7 +1143,7 @@ async fn main() {
         .expect("GAME_NOTES_CHANNEL_ID must be a valid u64");
 
     tokio::spawn(game_notes_watcher::run(
-        shop_pool,
+        pool,
         Arc::clone(&client.http),
         game_notes_channel_id,
     ));
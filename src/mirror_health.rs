@@ -0,0 +1,198 @@
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serenity::builder::CreateCommand;
+use serenity::model::application::CommandInteraction;
+use serenity::model::Permissions;
+use serenity::prelude::*;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tokio::time::{sleep, Duration};
+
+use crate::commands;
+use crate::link_rewriter::LinkRewriter;
+
+const DEFAULT_PROBE_INTERVAL_SECS: u64 = 300; // 5 minutes
+const PROBE_TIMEOUT_SECS: u64 = 10;
+
+#[derive(Debug, Clone)]
+pub struct MirrorStatus {
+    pub up: bool,
+    pub checked_at: DateTime<Utc>,
+    pub detail: String,
+}
+
+/// Last probe result per mirror host, shared between the prober and the rewriter.
+#[derive(Debug, Clone, Default)]
+pub struct MirrorHealth {
+    statuses: Arc<RwLock<HashMap<String, MirrorStatus>>>,
+}
+
+impl MirrorHealth {
+    /// Hosts that haven't been probed yet count as up.
+    pub fn is_up(&self, host: &str) -> bool {
+        self.statuses
+            .read()
+            .unwrap()
+            .get(host)
+            .is_none_or(|status| status.up)
+    }
+
+    pub fn status(&self, host: &str) -> Option<MirrorStatus> {
+        self.statuses.read().unwrap().get(host).cloned()
+    }
+
+    pub(crate) fn record(&self, host: &str, status: MirrorStatus) {
+        self.statuses.write().unwrap().insert(host.to_string(), status);
+    }
+}
+
+/// A mirror is up if it answers at all without a server error. Plenty of them return 403 or
+/// a redirect to a bare root request, which is fine for our purposes.
+async fn probe(client: &Client, host: &str) -> MirrorStatus {
+    let (up, detail) = match client
+        .get(format!("https://{}/", host))
+        .header("User-Agent", "MadiBot/1.0")
+        .send()
+        .await
+    {
+        Ok(resp) if resp.status().is_server_error() => (false, format!("HTTP {}", resp.status())),
+        Ok(resp) => (true, format!("HTTP {}", resp.status())),
+        Err(e) if e.is_timeout() => (false, "timed out".to_string()),
+        Err(e) if e.is_connect() => (false, "connection failed".to_string()),
+        Err(e) => (false, e.to_string()),
+    };
+    MirrorStatus {
+        up,
+        checked_at: Utc::now(),
+        detail,
+    }
+}
+
+pub async fn run(health: MirrorHealth, hosts: Vec<String>) {
+    let interval_secs = std::env::var("MIRROR_PROBE_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_PROBE_INTERVAL_SECS);
+
+    let client = match Client::builder()
+        .timeout(Duration::from_secs(PROBE_TIMEOUT_SECS))
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("[mirror_health] Failed to build HTTP client: {}", e);
+            return;
+        }
+    };
+
+    loop {
+        for host in &hosts {
+            let status = probe(&client, host).await;
+            let was_up = health.is_up(host);
+            if status.up != was_up {
+                println!(
+                    "[mirror_health] {} is now {} ({})",
+                    host,
+                    if status.up { "UP" } else { "DOWN" },
+                    status.detail
+                );
+            }
+            health.record(host, status);
+        }
+
+        sleep(Duration::from_secs(interval_secs)).await;
+    }
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("mirrors")
+        .description("Show which link-rewrite mirrors are up")
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+}
+
+/// Probe errors can be long; the status line only needs the gist.
+const MAX_DETAIL_CHARS: usize = 80;
+
+fn format_status(health: &MirrorHealth, rewriter: &LinkRewriter) -> String {
+    let mut lines = vec!["🪞 **Link mirror status**".to_string()];
+    let total: usize = rewriter.rules().iter().map(|rule| rule.mirror_hosts.len()).sum();
+    let mut shown = 0;
+    'rules: for rule in rewriter.rules() {
+        let active = rewriter.pick_mirror(rule);
+        let mut rule_lines = vec![format!("**{}**", rule.name)];
+        for host in &rule.mirror_hosts {
+            let marker = if Some(host.as_str()) == active { " ← in use" } else { "" };
+            let line = match health.status(host) {
+                Some(status) => format!(
+                    "{} `{}` — {}, checked <t:{}:R>{}",
+                    if status.up { "✅" } else { "❌" },
+                    host,
+                    status.detail.chars().take(MAX_DETAIL_CHARS).collect::<String>(),
+                    status.checked_at.timestamp(),
+                    marker
+                ),
+                None => format!("❔ `{}` — not probed yet{}", host, marker),
+            };
+            rule_lines.push(line);
+        }
+        for line in rule_lines {
+            // Stay under Discord's 2000 character message limit
+            if lines.iter().map(|l| l.chars().count() + 1).sum::<usize>() + line.chars().count() > 1900 {
+                // Don't leave a rule heading with none of its mirrors under it
+                if lines.last().is_some_and(|last| last.starts_with("**")) {
+                    lines.pop();
+                }
+                lines.push(format!("…and {} more mirror(s)", total - shown));
+                break 'rules;
+            }
+            if !line.starts_with("**") {
+                shown += 1;
+            }
+            lines.push(line);
+        }
+    }
+    lines.join("\n")
+}
+
+pub async fn run_command(
+    ctx: &Context,
+    command: &CommandInteraction,
+    health: &MirrorHealth,
+    rewriter: &LinkRewriter,
+) -> serenity::Result<()> {
+    commands::reply_ephemeral(ctx, command, format_status(health, rewriter)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::link_rewriter::RewriteRule;
+
+    #[test]
+    fn status_is_capped_under_the_message_limit() {
+        let rules: Vec<RewriteRule> = (0..8)
+            .map(|i| RewriteRule {
+                name: format!("site{}", i),
+                source_hosts: vec![format!("site{}.com", i)],
+                subdomains: Vec::new(),
+                keep_subdomain: false,
+                mirror_hosts: (0..6).map(|j| format!("mirror{}-{}.example.net", i, j)).collect(),
+                path_rewrite: None,
+            })
+            .collect();
+        let health = MirrorHealth::default();
+        for rule in &rules {
+            for host in &rule.mirror_hosts {
+                let detail = format!("error sending request for url (https://{}/): {}", host, "x".repeat(200));
+                health.record(host, MirrorStatus { up: false, checked_at: Utc::now(), detail });
+            }
+        }
+        let rewriter = LinkRewriter::new(rules, health.clone());
+
+        let status = format_status(&health, &rewriter);
+        assert!(status.chars().count() <= 2000, "{} characters", status.chars().count());
+        let shown = status.lines().filter(|line| line.starts_with('❌')).count();
+        assert!(shown > 0);
+        assert!(status.ends_with(&format!("…and {} more mirror(s)", 48 - shown)));
+    }
+}
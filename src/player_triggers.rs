@@ -0,0 +1,316 @@
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tokio::time::{sleep, Duration};
+
+use crate::roster;
+use crate::triggers::{EmojiSpec, MatchType, TriggerAction, TriggerRule};
+
+const DEFAULT_RELOAD_SECS: u64 = 300;
+const DEFAULT_REACTIONS: [&str; 6] = ["⚽", "🔥", "👏", "💪", "🙌", "☀️"];
+/// Most players one message can react for, so a lineup post doesn't become a wall of emoji.
+const MAX_PLAYERS_PER_MESSAGE: usize = 3;
+/// Player reactions run after every configured rule.
+const PLAYER_PRIORITY: i32 = 0;
+
+/// A current roster member and how to react when they're mentioned.
+#[derive(Debug, Clone)]
+pub struct Player {
+    pub name: String,
+    pub number: Option<i32>,
+    pub nicknames: Vec<String>,
+    pub reactions: Vec<Vec<EmojiSpec>>,
+    pub min_reactions: usize,
+    pub max_reactions: usize,
+    pub match_first_name: bool,
+    pub match_last_name: bool,
+}
+
+impl Player {
+    fn rule(&self) -> TriggerRule {
+        TriggerRule {
+            id: 0,
+            name: format!("player: {}", self.name),
+            guild_id: None,
+            match_type: MatchType::Phrase,
+            pattern: self.name.clone(),
+            actions: vec![TriggerAction::RandomPick {
+                options: self.reactions.clone(),
+                min: self.min_reactions,
+                max: self.max_reactions,
+            }],
+            priority: PLAYER_PRIORITY,
+            stop_processing: false,
+        }
+    }
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase())
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Lookup tables from names, nicknames and jersey numbers to players.
+#[derive(Debug, Default)]
+struct PlayerIndex {
+    players: Vec<Player>,
+    words: HashMap<String, usize>,
+    phrases: Vec<(Vec<String>, usize)>,
+    numbers: HashMap<i32, usize>,
+}
+
+impl PlayerIndex {
+    fn build(players: Vec<Player>) -> Self {
+        let mut words_to: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut phrases = Vec::new();
+        let mut numbers = HashMap::new();
+
+        for (i, player) in players.iter().enumerate() {
+            let name = words(&player.name);
+            let mut single: Vec<String> = Vec::new();
+            if name.len() > 1 {
+                phrases.push((name.clone(), i));
+                if player.match_first_name {
+                    single.push(name[0].clone());
+                }
+                if player.match_last_name {
+                    single.push(name[name.len() - 1].clone());
+                }
+            } else {
+                single.extend(name);
+            }
+            for nickname in &player.nicknames {
+                let nickname = words(nickname);
+                match nickname.len() {
+                    0 => {}
+                    1 => single.extend(nickname),
+                    _ => phrases.push((nickname, i)),
+                }
+            }
+            for word in single {
+                let owners = words_to.entry(word).or_default();
+                if !owners.contains(&i) {
+                    owners.push(i);
+                }
+            }
+            if let Some(number) = player.number {
+                numbers.insert(number, i);
+            }
+        }
+
+        // "Sam" on a roster with two Sams can't tell them apart; full names still work
+        let mut words = HashMap::new();
+        for (word, owners) in words_to {
+            match owners.as_slice() {
+                [only] => {
+                    words.insert(word, *only);
+                }
+                _ => println!("[player_triggers] '{}' is shared by {} players, ignoring it", word, owners.len()),
+            }
+        }
+
+        PlayerIndex {
+            players,
+            words,
+            phrases,
+            numbers,
+        }
+    }
+
+    /// Players mentioned in `content`, in the order they're first mentioned.
+    fn mentioned(&self, content: &str) -> Vec<&Player> {
+        let tokens = words(content);
+        let mut found: Vec<(usize, usize)> = Vec::new(); // (position, player)
+
+        for (pos, token) in tokens.iter().enumerate() {
+            if let Some(&i) = self.words.get(token) {
+                found.push((pos, i));
+            }
+        }
+        for (phrase, i) in &self.phrases {
+            if let Some(pos) = tokens.windows(phrase.len()).position(|w| w == phrase.as_slice()) {
+                found.push((pos, *i));
+            }
+        }
+        // "#9" survives only in the raw text; `words` strips the '#'
+        for (pos, raw) in content.split_whitespace().enumerate() {
+            let number = raw
+                .strip_prefix('#')
+                .map(|rest| rest.trim_end_matches(|c: char| !c.is_ascii_digit()))
+                .and_then(|digits| digits.parse::<i32>().ok());
+            if let Some(&i) = number.and_then(|n| self.numbers.get(&n)) {
+                found.push((pos, i));
+            }
+        }
+
+        found.sort();
+        let mut players: Vec<usize> = Vec::new();
+        for (_, i) in found {
+            if !players.contains(&i) {
+                players.push(i);
+            }
+        }
+        players
+            .into_iter()
+            .take(MAX_PLAYERS_PER_MESSAGE)
+            .map(|i| &self.players[i])
+            .collect()
+    }
+}
+
+/// The current roster's mention triggers.
+#[derive(Debug, Clone, Default)]
+pub struct PlayerTriggers {
+    index: Arc<RwLock<PlayerIndex>>,
+}
+
+impl PlayerTriggers {
+    /// Add a rule for each player mentioned in `content`, unless an already-fired rule
+    /// stops processing.
+    pub fn extend_fired(&self, fired: &mut Vec<TriggerRule>, content: &str) {
+        if fired.last().is_some_and(|rule| rule.stop_processing) {
+            return;
+        }
+        let index = self.index.read().unwrap();
+        fired.extend(index.mentioned(content).into_iter().map(Player::rule));
+    }
+
+    pub fn players(&self) -> Vec<Player> {
+        self.index.read().unwrap().players.clone()
+    }
+
+    /// Re-read the roster and per-player settings now.
+    pub async fn reload(&self, pool: &PgPool) -> anyhow::Result<usize> {
+        let players = fetch_players(pool).await?;
+        let count = players.len();
+        *self.index.write().unwrap() = PlayerIndex::build(players);
+        Ok(count)
+    }
+}
+
+pub async fn ensure_table(pool: &PgPool) -> anyhow::Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS player_triggers (
+            player_name      TEXT PRIMARY KEY,
+            nicknames        TEXT[] NOT NULL DEFAULT '{}',
+            reactions        JSONB,
+            min_reactions    INTEGER NOT NULL DEFAULT 1,
+            max_reactions    INTEGER NOT NULL DEFAULT 2,
+            match_first_name BOOLEAN NOT NULL DEFAULT TRUE,
+            match_last_name  BOOLEAN NOT NULL DEFAULT TRUE,
+            enabled          BOOLEAN NOT NULL DEFAULT TRUE
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Save a player's nicknames and reaction pool. `None` leaves that setting unchanged.
+pub async fn upsert_settings(
+    pool: &PgPool,
+    player_name: &str,
+    nicknames: Option<&[String]>,
+    reactions: Option<&[Vec<EmojiSpec>]>,
+    enabled: Option<bool>,
+) -> anyhow::Result<()> {
+    let reactions = reactions.map(serde_json::to_string).transpose()?;
+    sqlx::query(
+        "INSERT INTO player_triggers (player_name, nicknames, reactions, enabled)
+         VALUES ($1, COALESCE($2, '{}'), $3::jsonb, COALESCE($4, TRUE))
+         ON CONFLICT (player_name) DO UPDATE SET
+            nicknames = COALESCE($2, player_triggers.nicknames),
+            reactions = COALESCE($3::jsonb, player_triggers.reactions),
+            enabled = COALESCE($4, player_triggers.enabled)",
+    )
+    .bind(player_name)
+    .bind(nicknames)
+    .bind(reactions)
+    .bind(enabled)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+type SettingsRow = (String, Vec<String>, Option<String>, i32, i32, bool, bool, bool);
+
+async fn fetch_players(pool: &PgPool) -> anyhow::Result<Vec<Player>> {
+    let roster = roster::fetch_all(pool).await?;
+    let rows = sqlx::query_as::<_, SettingsRow>(
+        "SELECT player_name, nicknames, reactions::text, min_reactions, max_reactions,
+                match_first_name, match_last_name, enabled
+         FROM player_triggers",
+    )
+    .fetch_all(pool)
+    .await?;
+    let settings: HashMap<String, SettingsRow> = rows
+        .into_iter()
+        .map(|row| (row.0.to_lowercase(), row))
+        .collect();
+
+    let default_reactions: Vec<Vec<EmojiSpec>> = DEFAULT_REACTIONS.iter().map(|e| vec![EmojiSpec::unicode(e)]).collect();
+    let mut players = Vec::new();
+    for roster_player in roster {
+        let mut player = Player {
+            name: roster_player.name,
+            number: roster_player.number,
+            nicknames: Vec::new(),
+            reactions: default_reactions.clone(),
+            min_reactions: 1,
+            max_reactions: 2,
+            match_first_name: true,
+            match_last_name: true,
+        };
+        if let Some((_, nicknames, reactions, min, max, first, last, enabled)) =
+            settings.get(&player.name.to_lowercase()).cloned()
+        {
+            if !enabled {
+                continue;
+            }
+            player.nicknames = nicknames;
+            if let Some(reactions) = reactions {
+                match serde_json::from_str(&reactions) {
+                    Ok(reactions) => player.reactions = reactions,
+                    Err(e) => eprintln!("[player_triggers] {} has invalid reactions: {}", player.name, e),
+                }
+            }
+            player.min_reactions = min.max(0) as usize;
+            player.max_reactions = max.max(0) as usize;
+            player.match_first_name = first;
+            player.match_last_name = last;
+        }
+        players.push(player);
+    }
+    Ok(players)
+}
+
+/// Load the roster once, then re-read it now and then so settings edited in the database
+/// take effect. Roster notifications also trigger a reload (see `reload`).
+pub async fn run(players: PlayerTriggers, pool: PgPool) {
+    let reload_secs = std::env::var("PLAYER_TRIGGER_RELOAD_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_RELOAD_SECS);
+
+    if let Err(e) = ensure_table(&pool).await {
+        eprintln!("[player_triggers] Failed to create player_triggers table: {}", e);
+    }
+
+    let mut loaded: Option<usize> = None;
+    loop {
+        match players.reload(&pool).await {
+            Ok(count) => {
+                if loaded != Some(count) {
+                    println!("[player_triggers] Loaded {} player(s)", count);
+                }
+                loaded = Some(count);
+            }
+            Err(e) => eprintln!("[player_triggers] Reload error: {}", e),
+        }
+        sleep(Duration::from_secs(reload_secs)).await;
+    }
+}
@@ -0,0 +1,275 @@
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::emoji_registry;
+use crate::triggers::{activated_emojis, EmojiSpec, WeightedOption};
+
+/// A rare extra reaction, rolled independently of the pool picks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpecialEvent {
+    pub name: String,
+    /// Chance in [0, 1] when this event's turn comes up.
+    pub probability: f64,
+    pub emojis: Vec<EmojiSpec>,
+}
+
+/// How a fan-reaction trigger picks its emoji. All randomness comes from the caller's RNG,
+/// so a seeded RNG gives the same reactions every time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReactionPolicy {
+    /// Options to pick from, without repeats; heavier options come up more often.
+    pub pool: Vec<WeightedOption>,
+    /// Weight of picking 1, 2, 3, … options from the pool.
+    pub pick_counts: Vec<u32>,
+    /// Tried in order after the picks; the first one that hits is added and the rest are
+    /// skipped.
+    #[serde(default)]
+    pub specials: Vec<SpecialEvent>,
+}
+
+impl ReactionPolicy {
+    /// The original "madi" behaviour: 1-3 picks from the fan pool, then 10% ACTIVATED,
+    /// otherwise a 20% knife.
+    pub fn madi() -> Self {
+        let mut pool: Vec<Vec<EmojiSpec>> = ["🥵", "😍", "💖", "🥹", "🤤", "😋", "🤠", "💪"]
+            .iter()
+            .map(|e| vec![EmojiSpec::unicode(e)])
+            .collect();
+        pool.push(spelled("madi"));
+        pool.push(spelled("yes"));
+
+        ReactionPolicy {
+            pool: pool
+                .into_iter()
+                .map(|emojis| WeightedOption { weight: 1, emojis })
+                .collect(),
+            pick_counts: vec![1, 1, 1],
+            specials: vec![
+                SpecialEvent {
+                    name: "activated".to_string(),
+                    probability: 0.10,
+                    emojis: activated_emojis(),
+                },
+                SpecialEvent {
+                    name: "knife".to_string(),
+                    probability: 0.20,
+                    emojis: vec![emoji_registry::MADI_KNIFE.spec()],
+                },
+            ],
+        }
+    }
+
+    /// Check the numbers before a policy is saved.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.pick_counts.iter().all(|&w| w == 0) {
+            return Err("pick_counts needs at least one non-zero weight".to_string());
+        }
+        if let Some(special) = self.specials.iter().find(|s| !(0.0..=1.0).contains(&s.probability)) {
+            return Err(format!("'{}' has probability {}, outside 0-1", special.name, special.probability));
+        }
+        Ok(())
+    }
+
+    /// How many pool options to pick, capped at the pool size.
+    pub fn pick_count<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        match WeightedIndex::new(&self.pick_counts) {
+            Ok(dist) => (dist.sample(rng) + 1).min(self.pool.len()),
+            Err(_) => 0,
+        }
+    }
+
+    /// Weighted picks without repeats: each pick removes the option from the draw.
+    pub fn pick_options<R: Rng + ?Sized>(&self, count: usize, rng: &mut R) -> Vec<&WeightedOption> {
+        let mut remaining: Vec<&WeightedOption> = self.pool.iter().filter(|o| o.weight > 0).collect();
+        let mut picked = Vec::new();
+        while picked.len() < count {
+            let Ok(dist) = WeightedIndex::new(remaining.iter().map(|o| o.weight)) else {
+                break;
+            };
+            picked.push(remaining.remove(dist.sample(rng)));
+        }
+        picked
+    }
+
+    /// The special event that fires this time, if any.
+    pub fn roll_special<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<&SpecialEvent> {
+        self.specials
+            .iter()
+            .find(|special| rng.gen_bool(special.probability.clamp(0.0, 1.0)))
+    }
+
+    /// Every reaction for one firing: the pool picks, then any special event.
+    pub fn choose<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<EmojiSpec> {
+        let count = self.pick_count(rng);
+        let mut emojis: Vec<EmojiSpec> = self
+            .pick_options(count, rng)
+            .into_iter()
+            .flat_map(|option| option.emojis.iter().cloned())
+            .collect();
+        if let Some(special) = self.roll_special(rng) {
+            emojis.extend(special.emojis.iter().cloned());
+        }
+        emojis
+    }
+
+    /// One-line summary for command output.
+    pub fn describe(&self) -> String {
+        let specials: Vec<String> = self
+            .specials
+            .iter()
+            .map(|s| format!("{} {:.0}%", s.name, s.probability * 100.0))
+            .collect();
+        format!(
+            "{} of {} option(s){}",
+            match self.pick_counts.len() {
+                0 | 1 => "1".to_string(),
+                n => format!("1-{}", n),
+            },
+            self.pool.len(),
+            if specials.is_empty() { String::new() } else { format!(", then {}", specials.join(" / ")) }
+        )
+    }
+}
+
+fn spelled(word: &str) -> Vec<EmojiSpec> {
+    crate::speller::spell(word)
+        .unwrap_or_default()
+        .iter()
+        .map(|e| EmojiSpec::unicode(e))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    const SAMPLES: usize = 10_000;
+
+    fn option(emoji: &str, weight: u32) -> WeightedOption {
+        WeightedOption {
+            weight,
+            emojis: vec![EmojiSpec::unicode(emoji)],
+        }
+    }
+
+    fn special(name: &str, probability: f64) -> SpecialEvent {
+        SpecialEvent {
+            name: name.to_string(),
+            probability,
+            emojis: vec![EmojiSpec::unicode("✨")],
+        }
+    }
+
+    fn policy(pool: Vec<WeightedOption>, pick_counts: Vec<u32>, specials: Vec<SpecialEvent>) -> ReactionPolicy {
+        ReactionPolicy {
+            pool,
+            pick_counts,
+            specials,
+        }
+    }
+
+    #[test]
+    fn pick_count_stays_in_bounds() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let madi = ReactionPolicy::madi();
+        let mut seen = [0usize; 4];
+        for _ in 0..SAMPLES {
+            let count = madi.pick_count(&mut rng);
+            assert!((1..=3).contains(&count), "{}", count);
+            seen[count] += 1;
+        }
+        // Equal weights: each count comes up about a third of the time
+        for count in 1..=3 {
+            assert!((2_800..3_900).contains(&seen[count]), "{:?}", seen);
+        }
+
+        // Capped at the pool size, never more than there is to pick
+        let small = policy(vec![option("🔥", 1), option("💪", 1)], vec![0, 0, 0, 1], Vec::new());
+        assert!((0..SAMPLES).all(|_| small.pick_count(&mut rng) == 2));
+
+        // No usable weights: nothing to pick
+        let none = policy(vec![option("🔥", 1)], vec![0, 0], Vec::new());
+        assert_eq!(none.pick_count(&mut rng), 0);
+    }
+
+    #[test]
+    fn pick_options_never_repeats() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let madi = ReactionPolicy::madi();
+        for _ in 0..SAMPLES {
+            let count = madi.pick_count(&mut rng);
+            let picked = madi.pick_options(count, &mut rng);
+            assert_eq!(picked.len(), count);
+            for (i, a) in picked.iter().enumerate() {
+                assert!(picked[i + 1..].iter().all(|b| !std::ptr::eq(*a, *b)));
+            }
+        }
+
+        // Asking for more than the pool holds stops when it runs out
+        let small = policy(vec![option("🔥", 1), option("💪", 1)], vec![1], Vec::new());
+        assert_eq!(small.pick_options(5, &mut rng).len(), 2);
+    }
+
+    #[test]
+    fn pick_options_respects_weights() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let weighted = policy(vec![option("light", 1), option("never", 0), option("heavy", 9)], vec![1], Vec::new());
+        let mut heavy = 0;
+        for _ in 0..SAMPLES {
+            let picked = weighted.pick_options(1, &mut rng);
+            assert_eq!(picked.len(), 1);
+            match &picked[0].emojis[0] {
+                EmojiSpec::Unicode { emoji } if emoji == "heavy" => heavy += 1,
+                EmojiSpec::Unicode { emoji } => assert_eq!(emoji, "light"),
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+        assert!((8_700..9_300).contains(&heavy), "{}", heavy);
+
+        // A zero-weight option is never picked, even when it's all that's left
+        assert_eq!(weighted.pick_options(3, &mut rng).len(), 2);
+    }
+
+    #[test]
+    fn roll_special_goes_in_order() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let name = |policy: &ReactionPolicy, rng: &mut StdRng| policy.roll_special(rng).map(|s| s.name.clone());
+
+        // A sure first event always wins over a sure second one
+        let both = policy(Vec::new(), vec![1], vec![special("first", 1.0), special("second", 1.0)]);
+        assert!((0..100).all(|_| name(&both, &mut rng).as_deref() == Some("first")));
+
+        // The second only gets its turn when the first misses
+        let skip = policy(Vec::new(), vec![1], vec![special("first", 0.0), special("second", 1.0)]);
+        assert!((0..100).all(|_| name(&skip, &mut rng).as_deref() == Some("second")));
+
+        let none = policy(Vec::new(), vec![1], vec![special("first", 0.0)]);
+        assert!((0..100).all(|_| name(&none, &mut rng).is_none()));
+
+        // madi: ACTIVATED 10%, otherwise knife 20% of the remaining 90% = 18%
+        let madi = ReactionPolicy::madi();
+        let (mut activated, mut knife) = (0, 0);
+        for _ in 0..SAMPLES {
+            match name(&madi, &mut rng).as_deref() {
+                Some("activated") => activated += 1,
+                Some("knife") => knife += 1,
+                _ => {}
+            }
+        }
+        assert!((850..1_150).contains(&activated), "{}", activated);
+        assert!((1_600..2_000).contains(&knife), "{}", knife);
+    }
+
+    #[test]
+    fn same_seed_same_reactions() {
+        let madi = ReactionPolicy::madi();
+        let run = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (0..50).map(|_| madi.choose(&mut rng)).collect::<Vec<_>>()
+        };
+        assert_eq!(run(7), run(7));
+    }
+}
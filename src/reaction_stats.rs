@@ -0,0 +1,375 @@
+use chrono::{DateTime, Duration, Utc};
+use serenity::builder::{CreateAllowedMentions, CreateCommand, CreateCommandOption, CreateMessage};
+use serenity::http::Http;
+use serenity::model::application::{CommandInteraction, CommandOptionType, ResolvedValue};
+use serenity::model::channel::{Channel, ReactionType};
+use serenity::model::id::{ChannelId, GuildId, MessageId, UserId};
+use serenity::prelude::*;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tokio::time::sleep;
+
+use crate::commands;
+
+const DEFAULT_RETENTION_DAYS: i64 = 90;
+const DEFAULT_STATS_DAYS: i64 = 30;
+const LEADERBOARD_SIZE: i64 = 10;
+/// How often the background task prunes and checks whether the weekly post is due.
+const CHECK_INTERVAL_SECS: u64 = 3600;
+
+/// Which message a reaction went on, and whose message or reaction set it off.
+#[derive(Debug, Clone, Copy)]
+pub struct ReactionContext {
+    pub guild_id: Option<GuildId>,
+    pub channel_id: ChannelId,
+    pub message_id: MessageId,
+    pub user_id: UserId,
+}
+
+pub async fn ensure_tables(pool: &PgPool) -> anyhow::Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS reaction_events (
+            id         BIGSERIAL PRIMARY KEY,
+            guild_id   BIGINT,
+            channel_id BIGINT NOT NULL,
+            message_id BIGINT NOT NULL,
+            user_id    BIGINT NOT NULL,
+            trigger    TEXT NOT NULL,
+            emoji      TEXT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS reaction_events_guild_created_idx
+         ON reaction_events (guild_id, created_at DESC)",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS reaction_events_message_idx ON reaction_events (message_id)")
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS reaction_leaderboard_posts (
+            channel_id BIGINT PRIMARY KEY,
+            posted_at  TIMESTAMPTZ NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Store each reaction the bot added, tagged with the trigger that added it.
+pub async fn record(
+    pool: &PgPool,
+    context: ReactionContext,
+    added: &[(String, ReactionType)],
+) -> anyhow::Result<()> {
+    for (trigger, emoji) in added {
+        sqlx::query(
+            "INSERT INTO reaction_events (guild_id, channel_id, message_id, user_id, trigger, emoji)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(context.guild_id.map(|g| g.get() as i64))
+        .bind(context.channel_id.get() as i64)
+        .bind(context.message_id.get() as i64)
+        .bind(context.user_id.get() as i64)
+        .bind(trigger)
+        .bind(emoji.to_string())
+        .execute(pool)
+        .await?;
+    }
+    Ok(())
+}
+
+/// `record`, logging instead of failing; stats should never get in the way of reacting.
+pub async fn record_logged(pool: &PgPool, context: ReactionContext, added: &[(String, ReactionType)]) {
+    if added.is_empty() {
+        return;
+    }
+    if let Err(e) = record(pool, context, added).await {
+        eprintln!("[reaction_stats] Failed to record reactions: {}", e);
+    }
+}
+
+/// The (trigger, emoji) pairs already recorded for a message, oldest first.
+pub async fn fired_on(pool: &PgPool, message_id: MessageId) -> anyhow::Result<Vec<(String, String)>> {
+    Ok(sqlx::query_as::<_, (String, String)>(
+        "SELECT trigger, emoji FROM reaction_events WHERE message_id = $1 ORDER BY id",
+    )
+    .bind(message_id.get() as i64)
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Drop a message's rows for triggers whose reactions were taken back.
+pub async fn forget(pool: &PgPool, message_id: MessageId, triggers: &[String]) -> anyhow::Result<()> {
+    sqlx::query("DELETE FROM reaction_events WHERE message_id = $1 AND trigger = ANY($2)")
+        .bind(message_id.get() as i64)
+        .bind(triggers)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+async fn top_triggers(
+    pool: &PgPool,
+    guild_id: GuildId,
+    since: DateTime<Utc>,
+) -> anyhow::Result<Vec<(String, i64)>> {
+    Ok(sqlx::query_as::<_, (String, i64)>(
+        "SELECT trigger, COUNT(*) FROM reaction_events
+         WHERE guild_id = $1 AND created_at >= $2
+         GROUP BY trigger ORDER BY COUNT(*) DESC, trigger
+         LIMIT $3",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(since)
+    .bind(LEADERBOARD_SIZE)
+    .fetch_all(pool)
+    .await?)
+}
+
+async fn top_emojis(
+    pool: &PgPool,
+    guild_id: GuildId,
+    since: DateTime<Utc>,
+    user: Option<UserId>,
+) -> anyhow::Result<Vec<(String, i64)>> {
+    Ok(sqlx::query_as::<_, (String, i64)>(
+        "SELECT emoji, COUNT(*) FROM reaction_events
+         WHERE guild_id = $1 AND created_at >= $2 AND ($3::BIGINT IS NULL OR user_id = $3)
+         GROUP BY emoji ORDER BY COUNT(*) DESC, emoji
+         LIMIT 5",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(since)
+    .bind(user.map(|u| u.get() as i64))
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Users ranked by how many bot reactions their messages set off.
+async fn top_users(pool: &PgPool, guild_id: GuildId, since: DateTime<Utc>) -> anyhow::Result<Vec<(i64, i64)>> {
+    Ok(sqlx::query_as::<_, (i64, i64)>(
+        "SELECT user_id, COUNT(*) FROM reaction_events
+         WHERE guild_id = $1 AND created_at >= $2
+         GROUP BY user_id ORDER BY COUNT(*) DESC, user_id
+         LIMIT $3",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(since)
+    .bind(LEADERBOARD_SIZE)
+    .fetch_all(pool)
+    .await?)
+}
+
+fn format_counts(rows: &[(String, i64)], separator: &str) -> String {
+    rows.iter()
+        .map(|(name, count)| format!("{} ×{}", name, count))
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+pub fn register() -> CreateCommand {
+    let days = || {
+        CreateCommandOption::new(CommandOptionType::Integer, "days", "How many days back (default 30)")
+            .min_int_value(1)
+            .max_int_value(365)
+    };
+    CreateCommand::new("stats")
+        .description("Reaction trigger statistics")
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "triggers", "Which triggers fire most")
+                .add_sub_option(days()),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "me", "How often you set the bot off")
+                .add_sub_option(days()),
+        )
+}
+
+async fn handle(command: &CommandInteraction, pool: &PgPool) -> anyhow::Result<String> {
+    let Some(guild_id) = command.guild_id else {
+        return Ok("This only works in a server.".to_string());
+    };
+    let options = command.data.options();
+    let Some((name, sub_options)) = commands::subcommand(&options) else {
+        return Ok("Unknown subcommand.".to_string());
+    };
+    let days = match commands::option(sub_options, "days") {
+        Some(ResolvedValue::Integer(days)) => *days,
+        _ => DEFAULT_STATS_DAYS,
+    };
+    let since = Utc::now() - Duration::days(days);
+
+    match name {
+        "triggers" => {
+            let triggers = top_triggers(pool, guild_id, since).await?;
+            if triggers.is_empty() {
+                return Ok(format!("No reactions in the last {} days.", days));
+            }
+            let mut lines = vec![format!("📊 **Top triggers, last {} days**", days)];
+            lines.extend(
+                triggers
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (trigger, count))| format!("{}. **{}** — {} reaction(s)", i + 1, trigger, count)),
+            );
+            let emojis = top_emojis(pool, guild_id, since, None).await?;
+            lines.push(format!("Most used: {}", format_counts(&emojis, "  ")));
+            Ok(lines.join("\n"))
+        }
+        "me" => {
+            let user_id = command.user.id.get() as i64;
+            let (total, rank) = sqlx::query_as::<_, (i64, i64)>(
+                "WITH counts AS (
+                    SELECT user_id, COUNT(*) AS n FROM reaction_events
+                    WHERE guild_id = $1 AND created_at >= $2
+                    GROUP BY user_id
+                 )
+                 SELECT COALESCE((SELECT n FROM counts WHERE user_id = $3), 0),
+                        1 + (SELECT COUNT(*) FROM counts
+                             WHERE n > COALESCE((SELECT n FROM counts WHERE user_id = $3), 0))",
+            )
+            .bind(guild_id.get() as i64)
+            .bind(since)
+            .bind(user_id)
+            .fetch_one(pool)
+            .await?;
+            if total == 0 {
+                return Ok(format!("You haven't set off any reactions in the last {} days.", days));
+            }
+
+            let triggers = sqlx::query_as::<_, (String, i64)>(
+                "SELECT trigger, COUNT(*) FROM reaction_events
+                 WHERE guild_id = $1 AND created_at >= $2 AND user_id = $3
+                 GROUP BY trigger ORDER BY COUNT(*) DESC, trigger
+                 LIMIT 5",
+            )
+            .bind(guild_id.get() as i64)
+            .bind(since)
+            .bind(user_id)
+            .fetch_all(pool)
+            .await?;
+            let emojis = top_emojis(pool, guild_id, since, Some(command.user.id)).await?;
+            Ok(format!(
+                "📊 In the last {} days you set off **{}** reaction(s), rank **#{}** in this server.\nTriggers: {}\nEmoji: {}",
+                days,
+                total,
+                rank,
+                format_counts(&triggers, ", "),
+                format_counts(&emojis, "  ")
+            ))
+        }
+        _ => Ok("Unknown subcommand.".to_string()),
+    }
+}
+
+pub async fn run_command(ctx: &Context, command: &CommandInteraction, pool: &PgPool) -> serenity::Result<()> {
+    let content = match handle(command, pool).await {
+        Ok(content) => content,
+        Err(e) => {
+            eprintln!("[reaction_stats] Command error: {}", e);
+            "Something went wrong reading stats.".to_string()
+        }
+    };
+    commands::reply_ephemeral(ctx, command, content).await
+}
+
+async fn leaderboard_message(pool: &PgPool, guild_id: GuildId) -> anyhow::Result<Option<String>> {
+    let since = Utc::now() - Duration::days(7);
+    let users = top_users(pool, guild_id, since).await?;
+    if users.is_empty() {
+        return Ok(None);
+    }
+    let mut lines = vec!["🏆 **This week's reaction leaderboard**".to_string()];
+    let medals = ["🥇", "🥈", "🥉"];
+    for (i, (user_id, count)) in users.iter().enumerate() {
+        let place = medals.get(i).map(|m| m.to_string()).unwrap_or_else(|| format!("{}.", i + 1));
+        lines.push(format!("{} <@{}> — {} reaction(s)", place, user_id, count));
+    }
+    let triggers = top_triggers(pool, guild_id, since).await?;
+    if let Some((trigger, count)) = triggers.first() {
+        lines.push(format!("Busiest trigger: **{}** ({})", trigger, count));
+    }
+    Ok(Some(lines.join("\n")))
+}
+
+async fn post_leaderboard_if_due(pool: &PgPool, http: &Http, channel_id: ChannelId) -> anyhow::Result<()> {
+    let last = sqlx::query_as::<_, (DateTime<Utc>,)>(
+        "SELECT posted_at FROM reaction_leaderboard_posts WHERE channel_id = $1",
+    )
+    .bind(channel_id.get() as i64)
+    .fetch_optional(pool)
+    .await?;
+    if last.is_some_and(|(posted_at,)| Utc::now() - posted_at < Duration::days(7)) {
+        return Ok(());
+    }
+
+    let Channel::Guild(channel) = channel_id.to_channel(http).await? else {
+        anyhow::bail!("channel {} isn't in a server", channel_id);
+    };
+    if let Some(content) = leaderboard_message(pool, channel.guild_id).await? {
+        // List names without pinging everyone on the board
+        let message = CreateMessage::new()
+            .content(content)
+            .allowed_mentions(CreateAllowedMentions::new());
+        channel_id.send_message(http, message).await?;
+        println!("[reaction_stats] Posted weekly leaderboard in {}", channel_id);
+    }
+
+    sqlx::query(
+        "INSERT INTO reaction_leaderboard_posts (channel_id, posted_at) VALUES ($1, NOW())
+         ON CONFLICT (channel_id) DO UPDATE SET posted_at = EXCLUDED.posted_at",
+    )
+    .bind(channel_id.get() as i64)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+async fn prune(pool: &PgPool, retention_days: i64) -> anyhow::Result<u64> {
+    let result = sqlx::query("DELETE FROM reaction_events WHERE created_at < $1")
+        .bind(Utc::now() - Duration::days(retention_days))
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+/// Drop rows older than REACTION_STATS_RETENTION_DAYS and, when LEADERBOARD_CHANNEL_ID is
+/// set, post the weekly leaderboard there.
+pub async fn run(pool: PgPool, http: Arc<Http>) {
+    let retention_days = std::env::var("REACTION_STATS_RETENTION_DAYS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_RETENTION_DAYS);
+    let leaderboard_channel = std::env::var("LEADERBOARD_CHANNEL_ID")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|id| *id != 0)
+        .map(ChannelId::new);
+
+    loop {
+        match prune(&pool, retention_days).await {
+            Ok(0) => {}
+            Ok(removed) => println!("[reaction_stats] Pruned {} old reaction event(s)", removed),
+            Err(e) => eprintln!("[reaction_stats] Prune error: {}", e),
+        }
+        if let Some(channel_id) = leaderboard_channel {
+            if let Err(e) = post_leaderboard_if_due(&pool, &http, channel_id).await {
+                eprintln!("[reaction_stats] Leaderboard error: {}", e);
+            }
+        }
+        sleep(tokio::time::Duration::from_secs(CHECK_INTERVAL_SECS)).await;
+    }
+}
@@ -0,0 +1,628 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use serde::Deserialize;
+use serde_json::Value;
+use serenity::builder::{CreateEmbed, CreateEmbedFooter};
+use serenity::model::Timestamp;
+use sqlx::PgPool;
+use std::fmt;
+
+const DEFAULT_ROSTER_TABLE: &str = "roster";
+
+/// Most changes in one digest message, well inside Discord's embed limits.
+pub const DIGEST_MAX_EVENTS: usize = 25;
+/// Discord's limit on an embed field's value.
+const FIELD_LIMIT: usize = 1024;
+
+/// The cursor row tracking what's been announced.
+const ANNOUNCE_CURSOR: &str = "announcements";
+
+/// A row of the roster table. Only the name is required; everything else is shown when the
+/// table has it.
+#[derive(Debug, Clone, Default)]
+pub struct RosterPlayer {
+    pub name: String,
+    pub number: Option<i32>,
+    pub position: Option<String>,
+    pub nationality: Option<String>,
+    pub previous_club: Option<String>,
+    pub headshot_url: Option<String>,
+    pub joined: Option<String>,
+}
+
+/// Pull a field from a roster row, trying each column name in turn.
+fn field<'a>(row: &'a Value, names: &[&str]) -> Option<&'a Value> {
+    names.iter().find_map(|name| row.get(name).filter(|v| !v.is_null()))
+}
+
+fn text_field(row: &Value, names: &[&str]) -> Option<String> {
+    let text = match field(row, names)? {
+        Value::String(s) => s.trim().to_string(),
+        Value::Number(n) => n.to_string(),
+        _ => return None,
+    };
+    (!text.is_empty()).then_some(text)
+}
+
+fn int_field(row: &Value, names: &[&str]) -> Option<i32> {
+    field(row, names).and_then(|v| match v {
+        Value::Number(n) => n.as_i64().map(|n| n as i32),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    })
+}
+
+impl RosterPlayer {
+    fn from_row(row: &Value) -> Option<Self> {
+        let name = text_field(row, &["name", "player_name"])?;
+        Some(RosterPlayer {
+            name,
+            number: int_field(row, &["number", "jersey_number"]),
+            position: text_field(row, &["position", "pos"]),
+            nationality: text_field(row, &["nationality", "nation", "country"]),
+            previous_club: text_field(row, &["previous_club", "prev_club", "former_club", "last_club"]),
+            headshot_url: text_field(row, &["headshot_url", "headshot", "photo_url", "image_url"])
+                .filter(|url| url.starts_with("http")),
+            joined: text_field(row, &["joined_at", "joined", "date_joined", "ao_datetime"]),
+        })
+    }
+}
+
+pub fn roster_table() -> String {
+    let table = std::env::var("ROSTER_TABLE").unwrap_or_else(|_| DEFAULT_ROSTER_TABLE.to_string());
+    // Spliced into SQL, so only plain (optionally schema-qualified) identifiers
+    if table.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.') && !table.is_empty() {
+        table
+    } else {
+        eprintln!("[roster] Ignoring invalid ROSTER_TABLE '{}'", table);
+        DEFAULT_ROSTER_TABLE.to_string()
+    }
+}
+
+/// Read the roster table as JSON rows, so column types and extra columns don't matter.
+pub async fn fetch_all(pool: &PgPool) -> anyhow::Result<Vec<RosterPlayer>> {
+    let rows = sqlx::query_as::<_, (String,)>(&format!("SELECT to_jsonb(r)::text FROM {} r", roster_table()))
+        .fetch_all(pool)
+        .await?;
+
+    let mut roster = Vec::new();
+    for (json,) in rows {
+        let row: Value = serde_json::from_str(&json)?;
+        if let Some(player) = RosterPlayer::from_row(&row) {
+            roster.push(player);
+        }
+    }
+    Ok(roster)
+}
+
+/// Look a player up by name, or by number when there is no name. A name that isn't on the
+/// roster finds nobody: a removed player's number may already belong to someone else.
+pub async fn find(pool: &PgPool, name: &str, number: Option<i32>) -> anyhow::Result<Option<RosterPlayer>> {
+    let roster = fetch_all(pool).await?;
+    let name = name.trim();
+    let player = if name.is_empty() {
+        roster.iter().find(|p| number.is_some() && p.number == number)
+    } else {
+        roster.iter().find(|p| p.name.eq_ignore_ascii_case(name))
+    };
+    Ok(player.cloned())
+}
+
+/// When the player joined: the roster table's own column if it has one, otherwise the
+/// latest time they were logged as added or loaned in.
+pub async fn joined_at(pool: &PgPool, player: &RosterPlayer) -> anyhow::Result<Option<DateTime<Utc>>> {
+    if let Some(joined) = player.joined.as_deref().and_then(parse_timestamp) {
+        return Ok(Some(joined));
+    }
+    let row = sqlx::query_as::<_, (DateTime<Utc>, Option<String>)>(
+        "SELECT created_at, payload->>'event_time'
+         FROM roster_change_log
+         WHERE payload->>'event_type' IN ('added', 'loan_in', 'loaned_in')
+           AND lower(payload->>'name') = lower($1)
+         ORDER BY id DESC
+         LIMIT 1",
+    )
+    .bind(&player.name)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(|(logged, event_time)| event_time.as_deref().and_then(parse_timestamp).unwrap_or(logged)))
+}
+
+/// The durable side of roster_changes: the database trigger appends every change to
+/// roster_change_log, and the cursor remembers the last entry the bot has handled, so
+/// changes made while the bot is offline are posted when it reconnects.
+pub async fn ensure_tables(pool: &PgPool) -> anyhow::Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS roster_change_log (
+            id         BIGSERIAL PRIMARY KEY,
+            payload    JSONB NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS roster_change_cursor (
+            name    TEXT PRIMARY KEY,
+            last_id BIGINT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // A fresh cursor starts at the end of the log rather than replaying its whole history
+    sqlx::query(
+        "INSERT INTO roster_change_cursor (name, last_id)
+         SELECT $1, COALESCE(MAX(id), 0) FROM roster_change_log
+         ON CONFLICT (name) DO NOTHING",
+    )
+    .bind(ANNOUNCE_CURSOR)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Logged changes after the cursor, oldest first, as (log id, payload).
+pub async fn pending_changes(pool: &PgPool) -> anyhow::Result<Vec<(i64, String)>> {
+    let rows = sqlx::query_as::<_, (i64, String)>(
+        "SELECT l.id, l.payload::text
+         FROM roster_change_log l, roster_change_cursor c
+         WHERE c.name = $1 AND l.id > c.last_id
+         ORDER BY l.id",
+    )
+    .bind(ANNOUNCE_CURSOR)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Mark everything up to `id` as handled. Never moves the cursor backwards.
+pub async fn advance_cursor(pool: &PgPool, id: i64) -> anyhow::Result<()> {
+    sqlx::query("UPDATE roster_change_cursor SET last_id = $2 WHERE name = $1 AND last_id < $2")
+        .bind(ANNOUNCE_CURSOR)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Whether a notification points at roster_change_log (it carries a `log_id`) rather than
+/// being the only copy of the change.
+pub fn is_logged(payload: &str) -> bool {
+    serde_json::from_str::<Value>(payload).is_ok_and(|v| v.get("log_id").is_some())
+}
+
+/// What happened to the player. Each type reads its own extra payload fields.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RosterEventKind {
+    Added,
+    Removed,
+    NumberChange { old: i32, new: i32 },
+    LoanIn { club: Option<String> },
+    LoanOut { club: Option<String> },
+    /// Moved onto the injury list, or the season-ending list.
+    InjuryList { season_ending: bool },
+    /// Back off either injury list.
+    InjuryReturn,
+    ContractExtension { until: Option<String> },
+    /// Named captain, vice-captain, etc.
+    Captaincy { role: String },
+    PositionChange { old: Option<String>, new: String },
+    /// A type this bot doesn't know yet; announced as a generic roster change.
+    Other { event_type: String },
+}
+
+/// A roster_changes notification.
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct RosterChangeEvent {
+    pub kind: RosterEventKind,
+    pub number: i32,
+    pub name: String,
+    pub ao_datetime: String,
+    pub event_time: String,
+}
+
+/// The fields every notification carries, whatever its type.
+#[derive(Debug, Deserialize)]
+struct Envelope {
+    event_type: String,
+    number: i32,
+    name: String,
+    #[serde(default)]
+    ao_datetime: String,
+    #[serde(default)]
+    event_time: String,
+}
+
+#[derive(Debug)]
+pub enum RosterEventError {
+    Invalid(serde_json::Error),
+    MissingField { event_type: String, field: &'static str },
+}
+
+impl fmt::Display for RosterEventError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RosterEventError::Invalid(e) => write!(f, "invalid payload: {}", e),
+            RosterEventError::MissingField { event_type, field } => {
+                write!(f, "'{}' event is missing '{}'", event_type, field)
+            }
+        }
+    }
+}
+
+fn parse_number_change(raw: &Value, envelope: &Envelope) -> Result<RosterEventKind, RosterEventError> {
+    let old = int_field(raw, &["old_number", "previous_number"]).ok_or_else(|| missing(envelope, "old_number"))?;
+    let new = int_field(raw, &["new_number"]).unwrap_or(envelope.number);
+    Ok(RosterEventKind::NumberChange { old, new })
+}
+
+fn parse_loan(raw: &Value, incoming: bool) -> RosterEventKind {
+    if incoming {
+        RosterEventKind::LoanIn {
+            club: text_field(raw, &["from_club", "club"]),
+        }
+    } else {
+        RosterEventKind::LoanOut {
+            club: text_field(raw, &["to_club", "club"]),
+        }
+    }
+}
+
+fn parse_injury_list(raw: &Value, season_ending: bool) -> RosterEventKind {
+    let season_ending = season_ending || field(raw, &["season_ending"]).and_then(Value::as_bool).unwrap_or(false);
+    RosterEventKind::InjuryList { season_ending }
+}
+
+fn parse_contract_extension(raw: &Value) -> RosterEventKind {
+    RosterEventKind::ContractExtension {
+        until: text_field(raw, &["until", "contract_until", "expires"]),
+    }
+}
+
+fn parse_captaincy(raw: &Value) -> RosterEventKind {
+    RosterEventKind::Captaincy {
+        role: text_field(raw, &["role"]).unwrap_or_else(|| "Captain".to_string()),
+    }
+}
+
+fn parse_position_change(raw: &Value, envelope: &Envelope) -> Result<RosterEventKind, RosterEventError> {
+    let new = text_field(raw, &["new_position", "position"]).ok_or_else(|| missing(envelope, "new_position"))?;
+    Ok(RosterEventKind::PositionChange {
+        old: text_field(raw, &["old_position", "previous_position"]),
+        new,
+    })
+}
+
+fn missing(envelope: &Envelope, field: &'static str) -> RosterEventError {
+    RosterEventError::MissingField {
+        event_type: envelope.event_type.clone(),
+        field,
+    }
+}
+
+impl RosterChangeEvent {
+    /// Parse a notification payload, reading the extra fields its event_type needs.
+    pub fn parse(payload: &str) -> Result<Self, RosterEventError> {
+        let raw: Value = serde_json::from_str(payload).map_err(RosterEventError::Invalid)?;
+        let envelope = Envelope::deserialize(&raw).map_err(RosterEventError::Invalid)?;
+
+        let kind = match envelope.event_type.as_str() {
+            "added" => RosterEventKind::Added,
+            "removed" => RosterEventKind::Removed,
+            "number_change" | "number_changed" => parse_number_change(&raw, &envelope)?,
+            "loan_in" | "loaned_in" => parse_loan(&raw, true),
+            "loan_out" | "loaned_out" => parse_loan(&raw, false),
+            "injury_list" | "injured" => parse_injury_list(&raw, false),
+            "season_ending" | "season_ending_injury" => parse_injury_list(&raw, true),
+            "injury_return" | "injury_activated" => RosterEventKind::InjuryReturn,
+            "contract_extension" | "extension" => parse_contract_extension(&raw),
+            "captaincy" | "captain" => parse_captaincy(&raw),
+            "position_change" | "position_changed" => parse_position_change(&raw, &envelope)?,
+            _ => {
+                eprintln!(
+                    "[roster] Unknown event type '{}', posting it as a generic change. Raw payload: {}",
+                    envelope.event_type, payload
+                );
+                RosterEventKind::Other {
+                    event_type: envelope.event_type.clone(),
+                }
+            }
+        };
+        Ok(RosterChangeEvent {
+            kind,
+            number: envelope.number,
+            name: envelope.name,
+            ao_datetime: envelope.ao_datetime,
+            event_time: envelope.event_time,
+        })
+    }
+}
+
+impl RosterEventKind {
+    /// The canonical event_type name, for logs.
+    pub fn event_type(&self) -> &str {
+        match self {
+            RosterEventKind::Added => "added",
+            RosterEventKind::Removed => "removed",
+            RosterEventKind::NumberChange { .. } => "number_change",
+            RosterEventKind::LoanIn { .. } => "loan_in",
+            RosterEventKind::LoanOut { .. } => "loan_out",
+            RosterEventKind::InjuryList { season_ending: false } => "injury_list",
+            RosterEventKind::InjuryList { season_ending: true } => "season_ending",
+            RosterEventKind::InjuryReturn => "injury_return",
+            RosterEventKind::ContractExtension { .. } => "contract_extension",
+            RosterEventKind::Captaincy { .. } => "captaincy",
+            RosterEventKind::PositionChange { .. } => "position_change",
+            RosterEventKind::Other { event_type } => event_type,
+        }
+    }
+
+    fn headline(&self) -> (&'static str, String) {
+        let (emoji, action) = match self {
+            RosterEventKind::Added => ("✅", "ADDED TO ROSTER".to_string()),
+            RosterEventKind::Removed => ("❌", "REMOVED FROM ROSTER".to_string()),
+            RosterEventKind::NumberChange { .. } => ("🔢", "NUMBER CHANGE".to_string()),
+            RosterEventKind::LoanIn { .. } => ("📥", "LOANED IN".to_string()),
+            RosterEventKind::LoanOut { .. } => ("📤", "LOANED OUT".to_string()),
+            RosterEventKind::InjuryList { season_ending: false } => ("🩹", "PLACED ON INJURY LIST".to_string()),
+            RosterEventKind::InjuryList { season_ending: true } => ("🚑", "PLACED ON SEASON-ENDING INJURY LIST".to_string()),
+            RosterEventKind::InjuryReturn => ("💪", "BACK FROM INJURY".to_string()),
+            RosterEventKind::ContractExtension { .. } => ("✍️", "CONTRACT EXTENSION".to_string()),
+            RosterEventKind::Captaincy { role } => ("©️", format!("NAMED {}", role.to_uppercase())),
+            RosterEventKind::PositionChange { .. } => ("🔄", "POSITION CHANGE".to_string()),
+            RosterEventKind::Other { .. } => ("ℹ️", "ROSTER CHANGE".to_string()),
+        };
+        (emoji, action)
+    }
+
+    /// The type-specific line under the player, if there's anything to say.
+    fn detail(&self) -> Option<String> {
+        match self {
+            RosterEventKind::LoanIn { club: Some(club) } => Some(format!("On loan from {}", club)),
+            RosterEventKind::LoanOut { club: Some(club) } => Some(format!("On loan to {}", club)),
+            RosterEventKind::ContractExtension { until: Some(until) } => Some(format!("Signed through {}", until)),
+            RosterEventKind::PositionChange { old: Some(old), new } => Some(format!("{} → {}", old, new)),
+            RosterEventKind::PositionChange { old: None, new } => Some(format!("Now playing {}", new)),
+            _ => None,
+        }
+    }
+
+    /// Which digest section the change goes under.
+    fn section(&self) -> DigestSection {
+        match self {
+            RosterEventKind::Added | RosterEventKind::LoanIn { .. } => DigestSection::Added,
+            RosterEventKind::Removed | RosterEventKind::LoanOut { .. } => DigestSection::Removed,
+            _ => DigestSection::Changed,
+        }
+    }
+
+    fn colour(&self) -> u32 {
+        match self {
+            RosterEventKind::Added | RosterEventKind::LoanIn { .. } | RosterEventKind::InjuryReturn => 0x2ECC71,
+            RosterEventKind::Removed | RosterEventKind::LoanOut { .. } => 0xE74C3C,
+            RosterEventKind::InjuryList { .. } => 0xE67E22,
+            RosterEventKind::ContractExtension { .. } | RosterEventKind::Captaincy { .. } => 0xF1C40F,
+            RosterEventKind::NumberChange { .. }
+            | RosterEventKind::PositionChange { .. }
+            | RosterEventKind::Other { .. } => 0x3498DB,
+        }
+    }
+}
+
+/// "**#9** - Name", or "**#9 → #10** - Name" for a number change.
+fn player_line(event: &RosterChangeEvent) -> String {
+    match &event.kind {
+        RosterEventKind::NumberChange { old, new } => format!("**#{} → #{}** - {}", old, new, event.name),
+        _ => format!("**#{}** - {}", event.number, event.name),
+    }
+}
+
+fn body(event: &RosterChangeEvent) -> String {
+    match event.kind.detail() {
+        Some(detail) => format!("{}\n{}", player_line(event), detail),
+        None => player_line(event),
+    }
+}
+
+pub fn format_roster_change_message(event: &RosterChangeEvent) -> String {
+    let (emoji, action) = event.kind.headline();
+    format!("{} **{}** {}\n{}", emoji, action, emoji, body(event))
+}
+
+/// Timestamps arrive as whatever Postgres rendered: RFC 3339 from JSON, the plain
+/// `2026-01-31 12:00:00` text form or a bare date (taken as UTC).
+pub fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Utc));
+    }
+    if let Ok(time) = DateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f%#z") {
+        return Some(time.with_timezone(&Utc));
+    }
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f"))
+        .or_else(|_| NaiveDate::parse_from_str(value, "%Y-%m-%d").map(|date| date.and_time(NaiveTime::MIN)))
+        .ok()
+        .map(|time| time.and_utc())
+}
+
+/// The announcement as an embed, filled in with whatever the roster table knows about the
+/// player. With no details it carries the same text as the plain message.
+pub fn announcement_embed(event: &RosterChangeEvent, player: Option<&RosterPlayer>) -> CreateEmbed {
+    let (emoji, action) = event.kind.headline();
+    let mut embed = CreateEmbed::new()
+        .title(format!("{} {} {}", emoji, action, emoji))
+        .description(body(event))
+        .colour(event.kind.colour())
+        .footer(CreateEmbedFooter::new("Roster update"));
+
+    if let Some(player) = player {
+        for (label, value) in [
+            ("Position", &player.position),
+            ("Nationality", &player.nationality),
+            ("Previous club", &player.previous_club),
+        ] {
+            if let Some(value) = value {
+                embed = embed.field(label, value, true);
+            }
+        }
+        if let Some(url) = &player.headshot_url {
+            embed = embed.thumbnail(url);
+        }
+    }
+    if let Some(time) = parse_timestamp(&event.event_time)
+        .and_then(|t| Timestamp::from_unix_timestamp(t.timestamp()).ok())
+    {
+        embed = embed.timestamp(time);
+    }
+    embed
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DigestSection {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl DigestSection {
+    const ALL: [DigestSection; 3] = [DigestSection::Added, DigestSection::Removed, DigestSection::Changed];
+
+    fn title(self) -> &'static str {
+        match self {
+            DigestSection::Added => "✅ Added",
+            DigestSection::Removed => "❌ Removed",
+            DigestSection::Changed => "ℹ️ Changed",
+        }
+    }
+}
+
+/// One line per change. Added and removed players are clear from their section; other
+/// changes say what happened.
+fn digest_line(event: &RosterChangeEvent) -> String {
+    let mut line = player_line(event);
+    if event.kind.section() == DigestSection::Changed {
+        let (emoji, action) = event.kind.headline();
+        let mut chars = action.chars();
+        let action: String = chars.next().into_iter().chain(chars.flat_map(char::to_lowercase)).collect();
+        line = format!("{} {}: {}", emoji, line, action);
+    }
+    match event.kind.detail() {
+        Some(detail) => format!("{} ({})", line, detail),
+        None => line,
+    }
+}
+
+fn digest_sections(events: &[RosterChangeEvent]) -> Vec<(&'static str, Vec<String>)> {
+    DigestSection::ALL
+        .iter()
+        .map(|&section| {
+            let lines = events
+                .iter()
+                .filter(|event| event.kind.section() == section)
+                .map(digest_line)
+                .collect();
+            (section.title(), lines)
+        })
+        .filter(|(_, lines): &(_, Vec<String>)| !lines.is_empty())
+        .collect()
+}
+
+fn digest_title(events: &[RosterChangeEvent]) -> String {
+    format!("ROSTER UPDATE - {} changes", events.len())
+}
+
+/// Plain-text digest, for logs.
+pub fn format_roster_digest(events: &[RosterChangeEvent]) -> String {
+    let mut message = format!("📋 **{}** 📋", digest_title(events));
+    for (title, lines) in digest_sections(events) {
+        message.push_str(&format!("\n\n**{}**\n{}", title, lines.join("\n")));
+    }
+    message
+}
+
+/// Several changes that arrived together, in one embed with a field per section. Long
+/// sections carry on into further fields.
+pub fn digest_embed(events: &[RosterChangeEvent]) -> CreateEmbed {
+    let mut embed = CreateEmbed::new()
+        .title(format!("📋 {} 📋", digest_title(events)))
+        .colour(0x3498DB)
+        .footer(CreateEmbedFooter::new("Roster update"));
+
+    for (title, lines) in digest_sections(events) {
+        let mut chunk = String::new();
+        let mut name = title.to_string();
+        for line in lines {
+            if !chunk.is_empty() && chunk.len() + line.len() + 1 > FIELD_LIMIT {
+                embed = embed.field(&name, &chunk, false);
+                name = format!("{} (cont.)", title);
+                chunk.clear();
+            }
+            if !chunk.is_empty() {
+                chunk.push('\n');
+            }
+            chunk.push_str(&line);
+        }
+        embed = embed.field(name, chunk, false);
+    }
+
+    if let Some(time) = events
+        .iter()
+        .filter_map(|event| parse_timestamp(&event.event_time))
+        .max()
+        .and_then(|t| Timestamp::from_unix_timestamp(t.timestamp()).ok())
+    {
+        embed = embed.timestamp(time);
+    }
+    embed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(payload: &str) -> RosterChangeEvent {
+        RosterChangeEvent::parse(payload).unwrap()
+    }
+
+    #[test]
+    fn typed_events() {
+        let event = parse(r#"{"event_type":"number_change","number":10,"old_number":"9","name":"A B"}"#);
+        assert_eq!(event.kind, RosterEventKind::NumberChange { old: 9, new: 10 });
+        assert_eq!(format_roster_change_message(&event), "🔢 **NUMBER CHANGE** 🔢\n**#9 → #10** - A B");
+
+        let event = parse(r#"{"event_type":"loaned_out","number":4,"to_club":"FC X","name":"C D"}"#);
+        assert_eq!(event.kind.event_type(), "loan_out");
+        assert_eq!(format_roster_change_message(&event), "📤 **LOANED OUT** 📤\n**#4** - C D\nOn loan to FC X");
+
+        let event = parse(r#"{"event_type":"injured","number":7,"season_ending":true,"name":"E F"}"#);
+        assert_eq!(event.kind, RosterEventKind::InjuryList { season_ending: true });
+    }
+
+    #[test]
+    fn added_and_removed_keep_their_format() {
+        let event = parse(r#"{"event_type":"added","number":9,"name":"A B","ao_datetime":"x","event_time":"y"}"#);
+        assert_eq!(format_roster_change_message(&event), "✅ **ADDED TO ROSTER** ✅\n**#9** - A B");
+    }
+
+    #[test]
+    fn unknown_types_post_as_a_generic_change() {
+        let event = parse(r#"{"event_type":"traded","number":5,"name":"G H"}"#);
+        assert_eq!(event.kind.event_type(), "traded");
+        assert_eq!(format_roster_change_message(&event), "ℹ️ **ROSTER CHANGE** ℹ️\n**#5** - G H");
+    }
+
+    #[test]
+    fn bad_payloads_are_errors() {
+        let missing = RosterChangeEvent::parse(r#"{"event_type":"number_change","number":10,"name":"A B"}"#);
+        assert_eq!(missing.unwrap_err().to_string(), "'number_change' event is missing 'old_number'");
+        assert!(RosterChangeEvent::parse(r#"{"event_type":"added","name":"A B"}"#).is_err());
+        assert!(RosterChangeEvent::parse("not json").is_err());
+    }
+}
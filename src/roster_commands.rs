@@ -0,0 +1,197 @@
+use serenity::builder::{
+    CreateAutocompleteResponse, CreateCommand, CreateCommandOption, CreateInteractionResponse,
+};
+use serenity::model::application::{CommandInteraction, CommandOptionType, ResolvedValue};
+use serenity::prelude::*;
+use sqlx::PgPool;
+
+use crate::commands;
+use crate::roster::{self, RosterPlayer};
+
+/// Players per /roster page.
+const PAGE_SIZE: usize = 20;
+/// Discord shows at most 25 autocomplete choices.
+const MAX_CHOICES: usize = 25;
+
+/// Goalkeepers first, then defence, midfield and attack; anything else after.
+fn position_rank(position: Option<&str>) -> usize {
+    let Some(position) = position else {
+        return 5;
+    };
+    let position = position.to_lowercase();
+    let starts = |prefixes: &[&str]| prefixes.iter().any(|prefix| position.starts_with(prefix));
+    match position.as_str() {
+        "gk" | "g" => 0,
+        "d" | "df" | "cb" | "lb" | "rb" | "fb" | "lwb" | "rwb" | "wb" => 1,
+        "m" | "mf" | "cm" | "dm" | "cdm" | "am" | "cam" | "lm" | "rm" => 2,
+        "f" | "fw" | "st" | "cf" | "lw" | "rw" | "w" => 3,
+        _ if starts(&["goal", "keeper"]) => 0,
+        _ if starts(&["def", "cent", "full", "wing-back", "wingback"]) => 1,
+        _ if starts(&["mid"]) => 2,
+        _ if starts(&["forw", "strik", "att", "wing"]) => 3,
+        _ => 4,
+    }
+}
+
+fn sorted_roster(mut players: Vec<RosterPlayer>) -> Vec<RosterPlayer> {
+    players.sort_by(|a, b| {
+        (position_rank(a.position.as_deref()), &a.position, a.number.is_none(), a.number, &a.name).cmp(&(
+            position_rank(b.position.as_deref()),
+            &b.position,
+            b.number.is_none(),
+            b.number,
+            &b.name,
+        ))
+    });
+    players
+}
+
+fn number_label(player: &RosterPlayer) -> String {
+    player.number.map(|n| format!("#{}", n)).unwrap_or_else(|| "#?".to_string())
+}
+
+/// Players matching what was typed: a number like `9` or `#9`, an exact name, or part of
+/// a name.
+fn search<'a>(players: &'a [RosterPlayer], query: &str) -> Vec<&'a RosterPlayer> {
+    let query = query.trim();
+    if let Ok(number) = query.trim_start_matches('#').parse::<i32>() {
+        return players.iter().filter(|p| p.number == Some(number)).collect();
+    }
+    if let Some(exact) = players.iter().find(|p| p.name.eq_ignore_ascii_case(query)) {
+        return vec![exact];
+    }
+    let query = query.to_lowercase();
+    players.iter().filter(|p| p.name.to_lowercase().contains(&query)).collect()
+}
+
+pub fn register_roster() -> CreateCommand {
+    CreateCommand::new("roster")
+        .description("The current squad, grouped by position")
+        .add_option(CreateCommandOption::new(CommandOptionType::Integer, "page", "Roster page").min_int_value(1))
+}
+
+pub fn register_player() -> CreateCommand {
+    CreateCommand::new("player")
+        .description("Look up a player on the roster")
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::String, "player", "Name or jersey number")
+                .required(true)
+                .set_autocomplete(true),
+        )
+}
+
+async fn handle_roster(command: &CommandInteraction, pool: &PgPool) -> anyhow::Result<String> {
+    let options = command.data.options();
+    let page = match commands::option(&options, "page") {
+        Some(ResolvedValue::Integer(page)) => (*page).max(1) as usize,
+        _ => 1,
+    };
+
+    let players = sorted_roster(roster::fetch_all(pool).await?);
+    if players.is_empty() {
+        return Ok("The roster is empty.".to_string());
+    }
+    let pages = players.len().div_ceil(PAGE_SIZE);
+    if page > pages {
+        return Ok(format!("There are only {} page(s) of players.", pages));
+    }
+
+    let mut lines = vec![format!("📋 **Roster** — {} player(s), page {} of {}", players.len(), page, pages)];
+    let mut group: Option<&str> = None;
+    for (i, player) in players.iter().enumerate().skip((page - 1) * PAGE_SIZE).take(PAGE_SIZE) {
+        let position = player.position.as_deref().unwrap_or("Unlisted");
+        if group != Some(position) {
+            // Say so when a group started on the previous page
+            let continued = i > 0 && players[i - 1].position.as_deref().unwrap_or("Unlisted") == position;
+            lines.push(format!("\n**{}**{}", position, if continued { " (cont.)" } else { "" }));
+            group = Some(position);
+        }
+        lines.push(format!("`{:>3}` {}", number_label(player), player.name));
+    }
+    if page < pages {
+        lines.push(format!("\nUse `page: {}` for more.", page + 1));
+    }
+    Ok(lines.join("\n"))
+}
+
+async fn handle_player(command: &CommandInteraction, pool: &PgPool) -> anyhow::Result<String> {
+    let options = command.data.options();
+    let query = match commands::option(&options, "player") {
+        Some(ResolvedValue::String(query)) => query.trim(),
+        _ => "",
+    };
+    if query.is_empty() {
+        return Ok("Give a player name or number.".to_string());
+    }
+
+    let players = roster::fetch_all(pool).await?;
+    let player = match search(&players, query).as_slice() {
+        [] => return Ok(format!("No player on the roster matches `{}`.", query)),
+        [player] => (*player).clone(),
+        matches => {
+            let names: Vec<String> = matches
+                .iter()
+                .take(10)
+                .map(|p| format!("{} {}", number_label(p), p.name))
+                .collect();
+            return Ok(format!("`{}` matches {} players: {}", query, matches.len(), names.join(", ")));
+        }
+    };
+
+    let mut lines = vec![format!("**{}** - {}", number_label(&player), player.name)];
+    for (label, value) in [
+        ("Position", &player.position),
+        ("Nationality", &player.nationality),
+        ("Previous club", &player.previous_club),
+    ] {
+        if let Some(value) = value {
+            lines.push(format!("{}: {}", label, value));
+        }
+    }
+    match roster::joined_at(pool, &player).await {
+        Ok(Some(joined)) => lines.push(format!("Joined: <t:{}:D>", joined.timestamp())),
+        Ok(None) => {}
+        Err(e) => eprintln!("[roster_commands] Join date lookup failed for {}: {}", player.name, e),
+    }
+    if let Some(url) = &player.headshot_url {
+        lines.push(url.clone());
+    }
+    Ok(lines.join("\n"))
+}
+
+pub async fn run_command(ctx: &Context, command: &CommandInteraction, pool: &PgPool) -> serenity::Result<()> {
+    let result = match command.data.name.as_str() {
+        "roster" => handle_roster(command, pool).await,
+        _ => handle_player(command, pool).await,
+    };
+    let content = match result {
+        Ok(content) => content,
+        Err(e) => {
+            eprintln!("[roster_commands] Command error: {}", e);
+            "Something went wrong reading the roster.".to_string()
+        }
+    };
+    commands::reply_ephemeral(ctx, command, content).await
+}
+
+/// Suggest roster players for /player as the name is typed.
+pub async fn autocomplete(ctx: &Context, interaction: &CommandInteraction, pool: &PgPool) -> serenity::Result<()> {
+    let typed = interaction.data.autocomplete().map(|option| option.value).unwrap_or_default();
+    let players = match roster::fetch_all(pool).await {
+        Ok(players) => sorted_roster(players),
+        Err(e) => {
+            eprintln!("[roster_commands] Autocomplete error: {}", e);
+            Vec::new()
+        }
+    };
+
+    let mut response = CreateAutocompleteResponse::new();
+    let matches = if typed.trim().is_empty() { players.iter().collect() } else { search(&players, typed) };
+    for player in matches.into_iter().take(MAX_CHOICES) {
+        let label: String = format!("{} {}", number_label(player), player.name).chars().take(100).collect();
+        response = response.add_string_choice(label, player.name.chars().take(100).collect::<String>());
+    }
+    interaction
+        .create_response(&ctx.http, CreateInteractionResponse::Autocomplete(response))
+        .await
+}
@@ -0,0 +1,424 @@
+use serenity::builder::{CreateCommand, CreateCommandOption, GetMessages};
+use serenity::model::application::{CommandInteraction, CommandOptionType, ResolvedValue};
+use serenity::model::channel::{Message, ReactionType};
+use serenity::model::id::MessageId;
+use serenity::prelude::*;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::OnceLock;
+
+use crate::commands;
+use crate::reaction_stats::{self, ReactionContext};
+
+/// Discord's per-message reaction limit.
+pub const MAX_REACTIONS: usize = 20;
+
+/// Glyphs for one character, best first. The regional indicator always leads; the rest
+/// stand in when a word repeats the letter.
+fn glyphs(c: char) -> &'static [&'static str] {
+    match c {
+        'a' => &["🇦", "🅰️"],
+        'b' => &["🇧", "🅱️"],
+        'c' => &["🇨", "©️", "☪️"],
+        'd' => &["🇩"],
+        'e' => &["🇪", "📧"],
+        'f' => &["🇫"],
+        'g' => &["🇬"],
+        'h' => &["🇭", "♓"],
+        'i' => &["🇮", "ℹ️"],
+        'j' => &["🇯"],
+        'k' => &["🇰"],
+        'l' => &["🇱"],
+        'm' => &["🇲", "Ⓜ️", "♏"],
+        'n' => &["🇳", "♑"],
+        'o' => &["🇴", "🅾️", "⭕", "0️⃣"],
+        'p' => &["🇵", "🅿️"],
+        'q' => &["🇶"],
+        'r' => &["🇷", "®️"],
+        's' => &["🇸", "💲", "5️⃣"],
+        't' => &["🇹", "✝️"],
+        'u' => &["🇺", "⛎"],
+        'v' => &["🇻", "♈"],
+        'w' => &["🇼"],
+        'x' => &["🇽", "❌", "✖️"],
+        'y' => &["🇾"],
+        'z' => &["🇿", "💤"],
+        '0' => &["0️⃣", "⭕"],
+        '1' => &["1️⃣"],
+        '2' => &["2️⃣"],
+        '3' => &["3️⃣"],
+        '4' => &["4️⃣"],
+        '5' => &["5️⃣"],
+        '6' => &["6️⃣"],
+        '7' => &["7️⃣"],
+        '8' => &["8️⃣"],
+        '9' => &["9️⃣"],
+        '#' => &["#️⃣"],
+        '*' => &["*️⃣"],
+        '!' => &["❗", "❕", "‼️"],
+        '?' => &["❓", "❔"],
+        _ => &[],
+    }
+}
+
+/// Single glyphs covering several characters; used only when single letters run out.
+const MULTI_GLYPHS: &[(&str, &str)] = &[
+    ("100", "💯"),
+    ("10", "🔟"),
+    ("abc", "🔤"),
+    ("ab", "🆎"),
+    ("cl", "🆑"),
+    ("cool", "🆒"),
+    ("free", "🆓"),
+    ("id", "🆔"),
+    ("new", "🆕"),
+    ("ng", "🆖"),
+    ("ok", "🆗"),
+    ("sos", "🆘"),
+    ("up", "🆙"),
+    ("vs", "🆚"),
+    ("atm", "🏧"),
+    ("wc", "🚾"),
+    ("tm", "™️"),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpellError {
+    Empty,
+    Unsupported(char),
+    /// Every glyph for this character is already used.
+    OutOfGlyphs(char),
+    TooLong(usize),
+    /// The search hit its step limit before finding a spelling or ruling one out.
+    TooComplex,
+}
+
+impl fmt::Display for SpellError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpellError::Empty => write!(f, "there's nothing to spell"),
+            SpellError::Unsupported(c) => write!(f, "there's no emoji for '{}'", c),
+            SpellError::OutOfGlyphs(c) => {
+                write!(f, "ran out of different emoji for '{}'", c.to_ascii_uppercase())
+            }
+            SpellError::TooLong(n) => {
+                write!(f, "it needs {} reactions and Discord allows {}", n, MAX_REACTIONS)
+            }
+            SpellError::TooComplex => write!(f, "it took too long to find a spelling"),
+        }
+    }
+}
+
+/// Upper bound on search steps. The memo keeps real words far below this; it only stops
+/// a pathological input from tying up the thread.
+const MAX_SEARCH_STEPS: usize = 100_000;
+
+/// Every distinct glyph, so a set of them fits in a `u128` bitmask.
+fn all_glyphs() -> &'static [&'static str] {
+    static ALL: OnceLock<Vec<&'static str>> = OnceLock::new();
+    ALL.get_or_init(|| {
+        let mut all: Vec<&'static str> = Vec::new();
+        let singles = ('a'..='z')
+            .chain('0'..='9')
+            .chain(['#', '*', '!', '?'])
+            .flat_map(|c| glyphs(c).iter().copied());
+        for glyph in singles.chain(MULTI_GLYPHS.iter().map(|(_, glyph)| *glyph)) {
+            if !all.contains(&glyph) {
+                all.push(glyph);
+            }
+        }
+        assert!(all.len() <= 128, "too many glyphs for the search bitmask");
+        all
+    })
+}
+
+fn glyph_bit(glyph: &str) -> u128 {
+    all_glyphs().iter().position(|g| *g == glyph).map_or(0, |i| 1 << i)
+}
+
+/// Depth-first search over glyph choices, preferring one glyph per character and the
+/// earliest glyph in each list. Failed (position, used glyphs) states are remembered,
+/// counting only glyphs the rest of the word could still use, so the search can't blow
+/// up on words that have no spelling.
+struct Search<'a> {
+    chars: &'a [char],
+    rest: Vec<String>,
+    /// Glyphs usable from each position onwards.
+    relevant: Vec<u128>,
+    failed: HashMap<(usize, u128), SpellError>,
+    steps: usize,
+    out: Vec<&'static str>,
+}
+
+impl<'a> Search<'a> {
+    fn new(chars: &'a [char]) -> Self {
+        let rest: Vec<String> = (0..=chars.len()).map(|i| chars[i..].iter().collect()).collect();
+        let mut relevant = vec![0u128; chars.len() + 1];
+        for i in (0..chars.len()).rev() {
+            let mut mask = relevant[i + 1];
+            for glyph in glyphs(chars[i]) {
+                mask |= glyph_bit(glyph);
+            }
+            for (letters, glyph) in MULTI_GLYPHS {
+                if rest[i].starts_with(letters) {
+                    mask |= glyph_bit(glyph);
+                }
+            }
+            relevant[i] = mask;
+        }
+        Search {
+            chars,
+            rest,
+            relevant,
+            failed: HashMap::new(),
+            steps: 0,
+            out: Vec::new(),
+        }
+    }
+
+    fn run(&mut self, pos: usize, used: u128) -> Result<(), SpellError> {
+        let Some(&c) = self.chars.get(pos) else {
+            return Ok(());
+        };
+        let key = (pos, used & self.relevant[pos]);
+        if let Some(e) = self.failed.get(&key) {
+            return Err(e.clone());
+        }
+        self.steps += 1;
+        if self.steps > MAX_SEARCH_STEPS {
+            return Err(SpellError::TooComplex);
+        }
+
+        let multi = MULTI_GLYPHS
+            .iter()
+            .filter(|(letters, _)| self.rest[pos].starts_with(letters))
+            .map(|(letters, glyph)| (letters.chars().count(), *glyph));
+        let choices: Vec<(usize, &'static str)> = glyphs(c).iter().map(|glyph| (1, *glyph)).chain(multi).collect();
+
+        let mut last_error = SpellError::OutOfGlyphs(c);
+        for (len, glyph) in choices {
+            let bit = glyph_bit(glyph);
+            if used & bit != 0 {
+                continue;
+            }
+            self.out.push(glyph);
+            match self.run(pos + len, used | bit) {
+                Ok(()) => return Ok(()),
+                // Out of steps says nothing about this state, so don't remember it
+                Err(SpellError::TooComplex) => return Err(SpellError::TooComplex),
+                Err(e) => last_error = e,
+            }
+            self.out.pop();
+        }
+
+        self.failed.insert(key, last_error.clone());
+        Err(last_error)
+    }
+}
+
+/// Spell `word` as a reaction sequence, avoiding emoji in `taken` (every reaction already
+/// on the message, custom ones included, since they all count towards the limit). Spaces are dropped. CPU-bound; async callers should use `spawn_blocking`.
+pub fn spell_avoiding(word: &str, taken: &[String]) -> Result<Vec<String>, SpellError> {
+    let chars: Vec<char> = word
+        .to_lowercase()
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    if chars.is_empty() {
+        return Err(SpellError::Empty);
+    }
+    if let Some(&c) = chars.iter().find(|c| glyphs(**c).is_empty()) {
+        return Err(SpellError::Unsupported(c));
+    }
+    // Every character takes a reaction (bar the odd multi-letter tile), so anything longer
+    // can't fit and isn't worth searching
+    let total = taken.len() + chars.len();
+    if total > MAX_REACTIONS {
+        return Err(SpellError::TooLong(total));
+    }
+
+    // Only taken emoji the speller could have picked matter
+    let used = glyphs_in_use(taken).iter().fold(0u128, |used, glyph| used | glyph_bit(glyph));
+    let mut search = Search::new(&chars);
+    search.run(0, used)?;
+    Ok(search.out.into_iter().map(str::to_string).collect())
+}
+
+pub fn spell(word: &str) -> Result<Vec<String>, SpellError> {
+    spell_avoiding(word, &[])
+}
+
+fn glyphs_in_use(taken: &[String]) -> Vec<&'static str> {
+    let all = ('a'..='z')
+        .chain('0'..='9')
+        .chain(['#', '*', '!', '?'])
+        .flat_map(|c| glyphs(c).iter().copied())
+        .chain(MULTI_GLYPHS.iter().map(|(_, glyph)| *glyph));
+    all.filter(|glyph| taken.iter().any(|t| t == glyph)).collect()
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("spell")
+        .description("Spell a word in emoji reactions")
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::String, "word", "What to spell")
+                .required(true)
+                .max_length(40),
+        )
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "message",
+            "Message link or ID in this channel (default: the latest message)",
+        ))
+}
+
+async fn target_message(
+    ctx: &Context,
+    command: &CommandInteraction,
+    reference: Option<&str>,
+) -> anyhow::Result<Option<Message>> {
+    match reference {
+        Some(reference) => {
+            // Accept a bare ID or the last path segment of a message link
+            let id: u64 = match reference.trim().rsplit('/').next().and_then(|id| id.parse().ok()) {
+                Some(id) => id,
+                None => return Ok(None),
+            };
+            Ok(command.channel_id.message(&ctx.http, MessageId::new(id)).await.ok())
+        }
+        None => {
+            let latest = command
+                .channel_id
+                .messages(&ctx.http, GetMessages::new().limit(1))
+                .await?;
+            Ok(latest.into_iter().next())
+        }
+    }
+}
+
+async fn handle(ctx: &Context, command: &CommandInteraction, pool: &PgPool) -> anyhow::Result<String> {
+    let options = command.data.options();
+    let word = match commands::option(&options, "word") {
+        Some(ResolvedValue::String(word)) => word.to_string(),
+        _ => String::new(),
+    };
+    let reference = match commands::option(&options, "message") {
+        Some(ResolvedValue::String(reference)) => Some(reference.to_string()),
+        _ => None,
+    };
+
+    let Some(msg) = target_message(ctx, command, reference.as_deref()).await? else {
+        return Ok("Couldn't find that message in this channel.".to_string());
+    };
+
+    // Custom emoji can't clash with a letter but still use up reaction slots
+    let taken: Vec<String> = msg.reactions.iter().map(|r| r.reaction_type.to_string()).collect();
+    let spelled = {
+        let word = word.clone();
+        tokio::task::spawn_blocking(move || spell_avoiding(&word, &taken)).await?
+    };
+    let sequence = match spelled {
+        Ok(sequence) => sequence,
+        Err(e) => return Ok(format!("Can't spell \"{}\": {}.", word, e)),
+    };
+
+    println!("[speller] {} spelled '{}' on message {}", command.user.name, word, msg.id);
+    let mut added = Vec::new();
+    let mut refused = false;
+    for emoji in &sequence {
+        let reaction = ReactionType::Unicode(emoji.clone());
+        if let Err(why) = msg.react(&ctx.http, reaction.clone()).await {
+            println!("[speller] Error adding reaction: {:?}", why);
+            refused = true;
+            break;
+        }
+        added.push(("spell".to_string(), reaction));
+    }
+    let context = ReactionContext {
+        guild_id: command.guild_id,
+        channel_id: msg.channel_id,
+        message_id: msg.id,
+        user_id: command.user.id,
+    };
+    reaction_stats::record_logged(pool, context, &added).await;
+
+    if refused {
+        return Ok("Discord refused one of the reactions; the spelling may be incomplete.".to_string());
+    }
+    Ok(format!("Spelled {} on {}", sequence.concat(), msg.link()))
+}
+
+pub async fn run_command(ctx: &Context, command: &CommandInteraction, pool: &PgPool) -> serenity::Result<()> {
+    let content = match handle(ctx, command, pool).await {
+        Ok(content) => content,
+        Err(e) => {
+            eprintln!("[speller] Command error: {}", e);
+            "Something went wrong spelling that.".to_string()
+        }
+    };
+    commands::reply_ephemeral(ctx, command, content).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn repeated_letters_use_substitutes() {
+        assert_eq!(spell("madi").unwrap(), ["🇲", "🇦", "🇩", "🇮"]);
+        assert_eq!(spell("Yes Yes").unwrap_err(), SpellError::OutOfGlyphs('y'));
+        assert_eq!(spell("boo").unwrap(), ["🇧", "🇴", "🅾️"]);
+        assert_eq!(spell("oooo").unwrap(), ["🇴", "🅾️", "⭕", "0️⃣"]);
+        // Taken reactions are skipped
+        assert_eq!(spell_avoiding("go", &["🇴".to_string()]).unwrap(), ["🇬", "🅾️"]);
+    }
+
+    #[test]
+    fn multi_letter_tiles_fill_in_when_letters_run_out() {
+        assert_eq!(spell("idid").unwrap(), ["🇮", "🇩", "🆔"]);
+        assert_eq!(spell("okok").unwrap(), ["🇴", "🇰", "🆗"]);
+    }
+
+    #[test]
+    fn out_of_glyphs() {
+        let e = spell("ddd").unwrap_err();
+        assert_eq!(e, SpellError::OutOfGlyphs('d'));
+        assert_eq!(e.to_string(), "ran out of different emoji for 'D'");
+        assert_eq!(spell_avoiding("d", &["🇩".to_string()]).unwrap_err(), SpellError::OutOfGlyphs('d'));
+    }
+
+    #[test]
+    fn too_long_is_rejected_up_front() {
+        assert_eq!(spell("abcdefghijklmnopqrstu").unwrap_err(), SpellError::TooLong(21));
+        let taken: Vec<String> = (0..18).map(|i| format!("custom{}", i)).collect();
+        assert_eq!(spell_avoiding("abc", &taken).unwrap_err(), SpellError::TooLong(21));
+        assert!(spell_avoiding("ab", &taken).is_ok());
+    }
+
+    #[test]
+    fn running_out_of_steps_is_its_own_error() {
+        let chars: Vec<char> = "abc".chars().collect();
+        let mut search = Search::new(&chars);
+        search.steps = MAX_SEARCH_STEPS;
+        assert_eq!(search.run(0, 0).unwrap_err(), SpellError::TooComplex);
+        assert!(search.failed.is_empty());
+    }
+
+    #[test]
+    fn other_errors() {
+        assert_eq!(spell("  ").unwrap_err(), SpellError::Empty);
+        assert_eq!(spell("hi~").unwrap_err(), SpellError::Unsupported('~'));
+    }
+
+    #[test]
+    fn unspellable_words_fail_fast() {
+        // Every letter but the last has a spare glyph, so a plain backtracking search tries
+        // every combination before giving up on the final "dd"
+        let started = Instant::now();
+        assert_eq!(spell("abcehimnoprstuvxzdd").unwrap_err(), SpellError::OutOfGlyphs('d'));
+        assert_eq!(spell("aabbcceehhiimmnnoodd").unwrap_err(), SpellError::OutOfGlyphs('d'));
+        assert!(started.elapsed() < Duration::from_secs(1), "took {:?}", started.elapsed());
+    }
+}
@@ -0,0 +1,436 @@
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandInteraction, CommandOptionType, ResolvedValue};
+use serenity::model::guild::Emoji;
+use serenity::model::Permissions;
+use serenity::prelude::*;
+use sqlx::PgPool;
+
+use crate::commands;
+use crate::player_triggers::{self, PlayerTriggers};
+use crate::speller;
+use crate::triggers::{self, EmojiSpec, MatchType, PlannedAction, TriggerAction, TriggerEngine, TriggerRule};
+
+/// Turn `🔥 <:AC:123> :TI:` into emoji specs, checking custom emoji against the guild's
+/// list. Returns a user-facing error for anything that isn't a usable emoji.
+fn parse_emoji_list(input: &str, guild_emojis: &[Emoji]) -> Result<Vec<EmojiSpec>, String> {
+    let mut specs = Vec::new();
+    for token in input.split_whitespace() {
+        let spec = if let Some(inner) = token.strip_prefix('<').and_then(|t| t.strip_suffix('>')) {
+            // <:name:id> or <a:name:id>
+            let parts: Vec<&str> = inner.split(':').collect();
+            let id: u64 = match parts.as_slice() {
+                ["" | "a", _, id] => id.parse().map_err(|_| format!("`{}` isn't a valid emoji.", token))?,
+                _ => return Err(format!("`{}` isn't a valid emoji.", token)),
+            };
+            let emoji = guild_emojis
+                .iter()
+                .find(|e| e.id.get() == id)
+                .ok_or_else(|| format!("{} isn't an emoji from this server.", token))?;
+            EmojiSpec::Custom {
+                id,
+                name: emoji.name.clone(),
+                animated: emoji.animated,
+            }
+        } else if let Some(name) = token.strip_prefix(':').and_then(|t| t.strip_suffix(':')) {
+            let emoji = guild_emojis
+                .iter()
+                .find(|e| e.name == name)
+                .ok_or_else(|| format!("There's no `:{}:` emoji in this server.", name))?;
+            EmojiSpec::Custom {
+                id: emoji.id.get(),
+                name: emoji.name.clone(),
+                animated: emoji.animated,
+            }
+        } else if is_unicode_emoji(token) {
+            EmojiSpec::unicode(token)
+        } else {
+            return Err(format!("`{}` isn't an emoji.", token));
+        };
+        specs.push(spec);
+    }
+    if specs.len() > speller::MAX_REACTIONS {
+        return Err(format!("Discord allows at most {} reactions per message.", speller::MAX_REACTIONS));
+    }
+    Ok(specs)
+}
+
+/// Whether `token` is made only of emoji characters and the joiners, variation selectors,
+/// skin tones, tags and keycap marks that build sequences out of them. Keycaps like #️⃣ are
+/// the one place ASCII may appear.
+fn is_unicode_emoji(token: &str) -> bool {
+    let is_pictograph = |c: char| {
+        matches!(
+            c as u32,
+            0x1F000..=0x1FAFF
+                | 0x2190..=0x21FF
+                | 0x2300..=0x23FF
+                | 0x24C2
+                | 0x25A0..=0x27BF
+                | 0x2900..=0x297F
+                | 0x2B00..=0x2BFF
+                | 0x00A9
+                | 0x00AE
+                | 0x203C
+                | 0x2049
+                | 0x2122
+                | 0x2139
+                | 0x3030
+                | 0x303D
+                | 0x3297
+                | 0x3299
+        )
+    };
+    let is_modifier = |c: char| matches!(c as u32, 0x200D | 0xFE0E | 0xFE0F | 0x20E3 | 0xE0020..=0xE007F);
+    let keycap = token.contains('\u{20E3}');
+    token.chars().any(|c| is_pictograph(c) || (keycap && c == '\u{20E3}'))
+        && token
+            .chars()
+            .all(|c| is_pictograph(c) || is_modifier(c) || (keycap && matches!(c, '0'..='9' | '#' | '*')))
+}
+
+/// Longest single line in a reply, so one long trigger can't crowd out the rest.
+const MAX_LINE_CHARS: usize = 1000;
+
+/// Join reply lines, stopping with "…and N more" before Discord's 2000 character limit.
+fn join_capped(lines: &[String]) -> String {
+    let mut kept: Vec<String> = Vec::new();
+    let mut length = 0;
+    for (i, line) in lines.iter().enumerate() {
+        let line = if line.chars().count() > MAX_LINE_CHARS {
+            format!("{}…", line.chars().take(MAX_LINE_CHARS).collect::<String>())
+        } else {
+            line.clone()
+        };
+        if length + line.chars().count() + 1 > 1900 {
+            kept.push(format!("…and {} more", lines.len() - i));
+            break;
+        }
+        length += line.chars().count() + 1;
+        kept.push(line);
+    }
+    kept.join("\n")
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("trigger")
+        .description("Manage reaction triggers")
+        .default_member_permissions(Permissions::MANAGE_MESSAGES)
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "add", "Add a reaction trigger")
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::String, "name", "Short name for the trigger")
+                        .required(true),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::String, "pattern", "Word, phrase or regex to match")
+                        .required(true),
+                )
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "emojis",
+                    "Reactions to add, in order, e.g. 🔥 :AC: :TI:",
+                ))
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "reply",
+                    "Text to reply with",
+                ))
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "spell",
+                    "Word to spell out in letter reactions",
+                ))
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::String, "match", "How to match (default: whole word)")
+                        .add_string_choice("whole word", "whole_word")
+                        .add_string_choice("substring", "substring")
+                        .add_string_choice("phrase", "phrase")
+                        .add_string_choice("regex", "regex"),
+                )
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::Integer,
+                    "priority",
+                    "Higher runs first (default 50)",
+                ))
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::Boolean,
+                    "stop",
+                    "Skip lower-priority triggers when this one fires (default false)",
+                )),
+        )
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "list",
+            "List the triggers active in this server",
+        ))
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "remove", "Remove a trigger")
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::Integer, "id", "Trigger id from /trigger list")
+                        .required(true),
+                ),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "player", "Set a player's nicknames and reactions")
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::String, "name", "Player's name as on the roster")
+                        .required(true),
+                )
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "emojis",
+                    "Reaction pool; each mention picks from these",
+                ))
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "nicknames",
+                    "Comma-separated nicknames",
+                ))
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::Boolean,
+                    "enabled",
+                    "React to mentions of this player (default true)",
+                )),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "test", "See which triggers a message would fire")
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::String, "text", "Message text to test")
+                        .required(true),
+                ),
+        )
+}
+
+fn format_rule(rule: &TriggerRule) -> String {
+    format!(
+        "`#{}` **{}** — {} `{}` → {} (priority {}{}{})",
+        rule.id,
+        rule.name,
+        rule.match_type.as_str(),
+        rule.pattern,
+        rule.actions.iter().map(TriggerAction::describe).collect::<Vec<_>>().join(", "),
+        rule.priority,
+        if rule.stop_processing { ", stops" } else { "" },
+        if rule.guild_id.is_none() { ", global" } else { "" },
+    )
+}
+
+async fn handle(
+    ctx: &Context,
+    command: &CommandInteraction,
+    pool: &PgPool,
+    engine: &TriggerEngine,
+    players: &PlayerTriggers,
+) -> anyhow::Result<String> {
+    let Some(guild_id) = command.guild_id else {
+        return Ok("This only works in a server.".to_string());
+    };
+    let options = command.data.options();
+    let Some((name, sub_options)) = commands::subcommand(&options) else {
+        return Ok("Unknown subcommand.".to_string());
+    };
+    let string = |key: &str| match commands::option(sub_options, key) {
+        Some(ResolvedValue::String(value)) => Some(value.trim().to_string()),
+        _ => None,
+    };
+
+    match name {
+        "add" => {
+            let rule_name = string("name").unwrap_or_default();
+            let pattern = string("pattern").unwrap_or_default();
+            if pattern.is_empty() {
+                return Ok("The pattern can't be empty.".to_string());
+            }
+            let match_type = string("match")
+                .and_then(|m| MatchType::parse(&m))
+                .unwrap_or(MatchType::WholeWord);
+            let priority = match commands::option(sub_options, "priority") {
+                Some(ResolvedValue::Integer(p)) => *p as i32,
+                _ => 50,
+            };
+            let stop_processing = matches!(commands::option(sub_options, "stop"), Some(ResolvedValue::Boolean(true)));
+
+            let mut actions = Vec::new();
+            if let Some(emojis) = string("emojis").filter(|e| !e.is_empty()) {
+                let guild_emojis = guild_id.emojis(&ctx.http).await?;
+                match parse_emoji_list(&emojis, &guild_emojis) {
+                    Ok(emojis) => actions.push(TriggerAction::Sequence { emojis }),
+                    Err(message) => return Ok(message),
+                }
+            }
+            if let Some(word) = string("spell").filter(|w| !w.is_empty()) {
+                let spelled = {
+                    let word = word.clone();
+                    tokio::task::spawn_blocking(move || speller::spell(&word)).await?
+                };
+                if let Err(e) = spelled {
+                    return Ok(format!("Can't spell \"{}\": {}.", word, e));
+                }
+                actions.push(TriggerAction::Spell { word });
+            }
+            if let Some(text) = string("reply").filter(|t| !t.is_empty()) {
+                actions.push(TriggerAction::Reply { text });
+            }
+            if actions.is_empty() {
+                return Ok("Give the trigger some `emojis`, a word to `spell`, or a `reply`.".to_string());
+            }
+
+            let mut rule = TriggerRule {
+                id: 0,
+                name: rule_name,
+                guild_id: Some(guild_id),
+                match_type,
+                pattern,
+                actions,
+                priority,
+                stop_processing,
+            };
+            if let Err(e) = triggers::validate(&rule) {
+                return Ok(format!("That pattern doesn't work: {}", e));
+            }
+            rule.id = triggers::insert_rule(pool, &rule).await?;
+            engine.reload(pool).await?;
+            println!("[trigger_commands] {} added trigger #{} '{}'", command.user.name, rule.id, rule.name);
+            Ok(join_capped(&[format!("Added {}", format_rule(&rule))]))
+        }
+        "list" => {
+            let rules = engine.rules_for(Some(guild_id));
+            if rules.is_empty() {
+                return Ok("No triggers are active here.".to_string());
+            }
+            let mut lines = vec!["⚡ **Reaction triggers** (highest priority first)".to_string()];
+            lines.extend(rules.iter().map(format_rule));
+            Ok(join_capped(&lines))
+        }
+        "remove" => {
+            let id = match commands::option(sub_options, "id") {
+                Some(ResolvedValue::Integer(id)) => *id,
+                _ => return Ok("Which trigger? Use the id from /trigger list.".to_string()),
+            };
+            let allow_global = command
+                .member
+                .as_ref()
+                .and_then(|member| member.permissions)
+                .is_some_and(|p| p.administrator());
+            match triggers::remove_rule(pool, id, guild_id, allow_global).await? {
+                Some(removed) => {
+                    engine.reload(pool).await?;
+                    println!("[trigger_commands] {} removed trigger #{} '{}'", command.user.name, id, removed);
+                    Ok(format!("Removed trigger `#{}` **{}**.", id, removed))
+                }
+                None => Ok(format!(
+                    "No trigger `#{}` here. Global triggers can only be removed by an administrator.",
+                    id
+                )),
+            }
+        }
+        "player" => {
+            let wanted = string("name").unwrap_or_default();
+            let Some(player) = players
+                .players()
+                .into_iter()
+                .find(|p| p.name.eq_ignore_ascii_case(&wanted))
+            else {
+                return Ok(format!("**{}** isn't on the current roster.", wanted));
+            };
+
+            let reactions = match string("emojis").filter(|e| !e.is_empty()) {
+                Some(emojis) => {
+                    let guild_emojis = guild_id.emojis(&ctx.http).await?;
+                    match parse_emoji_list(&emojis, &guild_emojis) {
+                        // Each emoji is its own option in the pool
+                        Ok(emojis) => Some(emojis.into_iter().map(|e| vec![e]).collect::<Vec<_>>()),
+                        Err(message) => return Ok(message),
+                    }
+                }
+                None => None,
+            };
+            let nicknames = string("nicknames").map(|n| {
+                n.split(',')
+                    .map(|nickname| nickname.trim().to_string())
+                    .filter(|nickname| !nickname.is_empty())
+                    .collect::<Vec<_>>()
+            });
+            let enabled = match commands::option(sub_options, "enabled") {
+                Some(ResolvedValue::Boolean(enabled)) => Some(*enabled),
+                _ => None,
+            };
+
+            player_triggers::upsert_settings(pool, &player.name, nicknames.as_deref(), reactions.as_deref(), enabled)
+                .await?;
+            players.reload(pool).await?;
+            println!("[trigger_commands] {} updated player triggers for {}", command.user.name, player.name);
+            Ok(format!("Updated mention reactions for **{}**.", player.name))
+        }
+        "test" => {
+            let text = string("text").unwrap_or_default();
+            let mut fired = engine.matching(&text, Some(guild_id));
+            players.extend_fired(&mut fired, &text);
+            if fired.is_empty() {
+                return Ok("No triggers would fire for that.".to_string());
+            }
+            let planned = TriggerEngine::plan(&fired, &mut rand::thread_rng());
+            let mut lines = vec!["Would fire:".to_string()];
+            lines.extend(fired.iter().map(format_rule));
+            lines.push("Sample result:".to_string());
+            for (_, action) in planned {
+                lines.push(match action {
+                    PlannedAction::React(reactions) => {
+                        reactions.iter().map(ToString::to_string).collect::<Vec<_>>().join(" ")
+                    }
+                    PlannedAction::Reply(text) => format!("reply \"{}\"", text),
+                });
+            }
+            Ok(join_capped(&lines))
+        }
+        _ => Ok("Unknown subcommand.".to_string()),
+    }
+}
+
+pub async fn run_command(
+    ctx: &Context,
+    command: &CommandInteraction,
+    pool: &PgPool,
+    engine: &TriggerEngine,
+    players: &PlayerTriggers,
+) -> serenity::Result<()> {
+    let content = match handle(ctx, command, pool, engine, players).await {
+        Ok(content) => content,
+        Err(e) => {
+            eprintln!("[trigger_commands] Command error: {}", e);
+            "Something went wrong updating triggers.".to_string()
+        }
+    };
+    commands::reply_ephemeral(ctx, command, content).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unicode_emoji_are_told_apart_from_text() {
+        for emoji in ["🔥", "❤️", "#️⃣", "1️⃣", "👍🏽", "👨‍👩‍👧", "🏴‍☠️", "🇦🇷", "™️", "⭕"] {
+            assert!(is_unicode_emoji(emoji), "{}", emoji);
+        }
+        for text in ["héllo", "ñ", "日本", "#", "1", "🔥a", "é🔥", "\u{FE0F}"] {
+            assert!(!is_unicode_emoji(text), "{}", text);
+        }
+        assert_eq!(parse_emoji_list("🔥 héllo", &[]).unwrap_err(), "`héllo` isn't an emoji.");
+        assert_eq!(parse_emoji_list("🔥 ⭕", &[]).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn replies_are_capped_under_the_message_limit() {
+        let mut lines = vec!["header".to_string()];
+        lines.extend((0..100).map(|i| format!("rule {} {}", i, "x".repeat(50))));
+        let joined = join_capped(&lines);
+        assert!(joined.chars().count() <= 2000);
+        let kept = joined.lines().count() - 1;
+        assert!(joined.ends_with(&format!("…and {} more", lines.len() - kept)));
+
+        let long = join_capped(&[format!("Added {}", "y".repeat(5000))]);
+        assert_eq!(long.chars().count(), MAX_LINE_CHARS + 1);
+        assert!(long.ends_with('…'));
+    }
+}
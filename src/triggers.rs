@@ -0,0 +1,589 @@
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serenity::builder::{CreateAllowedMentions, CreateMessage};
+use serenity::model::channel::{Message, ReactionType};
+use serenity::model::id::{EmojiId, GuildId};
+use serenity::prelude::*;
+use sqlx::PgPool;
+use std::sync::{Arc, RwLock};
+use tokio::time::{sleep, Duration};
+
+use crate::emoji_registry::{self, EmojiRef, EmojiRegistry};
+use crate::reaction_policy::ReactionPolicy;
+use crate::speller;
+
+const DEFAULT_RELOAD_SECS: u64 = 60;
+
+/// A reaction emoji as stored in the rules table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EmojiSpec {
+    Unicode {
+        emoji: String,
+    },
+    Custom {
+        id: u64,
+        name: String,
+        #[serde(default)]
+        animated: bool,
+    },
+    /// A guild emoji looked up by name in whichever guild the message is in, trying each
+    /// name in turn, then the unicode fallback.
+    Named {
+        names: Vec<String>,
+        #[serde(default)]
+        fallback: Option<String>,
+    },
+}
+
+impl EmojiSpec {
+    pub fn unicode(emoji: &str) -> Self {
+        EmojiSpec::Unicode {
+            emoji: emoji.to_string(),
+        }
+    }
+
+    /// The reaction as stored, without any guild lookup. Named emoji only have their
+    /// fallback.
+    pub fn to_reaction(&self) -> Option<ReactionType> {
+        match self {
+            EmojiSpec::Unicode { emoji } => Some(ReactionType::Unicode(emoji.clone())),
+            EmojiSpec::Custom { id, name, animated } => Some(ReactionType::Custom {
+                animated: *animated,
+                id: EmojiId::new(*id),
+                name: Some(name.clone()),
+            }),
+            EmojiSpec::Named { fallback, .. } => fallback.clone().map(ReactionType::Unicode),
+        }
+    }
+}
+
+impl std::fmt::Display for EmojiSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EmojiSpec::Unicode { emoji } => write!(f, "{}", emoji),
+            EmojiSpec::Custom { id, name, animated } => {
+                write!(f, "<{}:{}:{}>", if *animated { "a" } else { "" }, name, id)
+            }
+            EmojiSpec::Named { names, .. } => write!(f, ":{}:", names.first().map(String::as_str).unwrap_or("?")),
+        }
+    }
+}
+
+fn join_emojis(emojis: &[EmojiSpec]) -> String {
+    emojis.iter().map(ToString::to_string).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchType {
+    /// Pattern appears anywhere, even inside a word ("activated" in "deactivated").
+    Substring,
+    /// Pattern equals one whole word, ignoring surrounding punctuation.
+    WholeWord,
+    /// Pattern's words appear consecutively as whole words ("madi parsons").
+    Phrase,
+    /// Case-insensitive regular expression.
+    Regex,
+}
+
+impl MatchType {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "substring" => Some(MatchType::Substring),
+            "whole_word" => Some(MatchType::WholeWord),
+            "phrase" => Some(MatchType::Phrase),
+            "regex" => Some(MatchType::Regex),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MatchType::Substring => "substring",
+            MatchType::WholeWord => "whole_word",
+            MatchType::Phrase => "phrase",
+            MatchType::Regex => "regex",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeightedOption {
+    pub weight: u32,
+    /// May be empty: a weighted chance of doing nothing.
+    pub emojis: Vec<EmojiSpec>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TriggerAction {
+    /// React with every emoji, in order.
+    Sequence { emojis: Vec<EmojiSpec> },
+    /// React with between `min` and `max` distinct options, chosen uniformly.
+    RandomPick {
+        options: Vec<Vec<EmojiSpec>>,
+        min: usize,
+        max: usize,
+    },
+    /// React with exactly one option, chosen by weight.
+    WeightedPick { options: Vec<WeightedOption> },
+    /// Post a text reply in the channel.
+    Reply { text: String },
+    /// Spell the word out in letter emoji.
+    Spell { word: String },
+    /// Weighted pool picks plus rare special events.
+    Policy(ReactionPolicy),
+}
+
+impl TriggerAction {
+    /// One-line summary for command output.
+    pub fn describe(&self) -> String {
+        match self {
+            TriggerAction::Sequence { emojis } => join_emojis(emojis),
+            TriggerAction::RandomPick { options, min, max } => format!(
+                "{}-{} of [{}]",
+                min,
+                max,
+                options.iter().map(|o| join_emojis(o)).collect::<Vec<_>>().join(" ")
+            ),
+            TriggerAction::WeightedPick { options } => format!(
+                "one of [{}]",
+                options
+                    .iter()
+                    .map(|o| {
+                        let emojis = if o.emojis.is_empty() { "nothing".to_string() } else { join_emojis(&o.emojis) };
+                        format!("{}×{}", o.weight, emojis)
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+            TriggerAction::Reply { text } => format!("reply \"{}\"", text),
+            TriggerAction::Spell { word } => format!("spell \"{}\"", word),
+            TriggerAction::Policy(policy) => policy.describe(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TriggerRule {
+    pub id: i64,
+    pub name: String,
+    /// None applies everywhere.
+    pub guild_id: Option<GuildId>,
+    pub match_type: MatchType,
+    pub pattern: String,
+    pub actions: Vec<TriggerAction>,
+    /// Higher runs first.
+    pub priority: i32,
+    /// Skip every lower-priority rule once this one fires.
+    pub stop_processing: bool,
+}
+
+/// A rule with its matcher prepared.
+#[derive(Debug)]
+struct CompiledRule {
+    rule: TriggerRule,
+    regex: Option<Regex>,
+    pattern_lower: String,
+}
+
+fn words(text: &str) -> Vec<&str> {
+    text.split_whitespace()
+        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()))
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+impl CompiledRule {
+    fn compile(rule: TriggerRule) -> anyhow::Result<Self> {
+        let regex = match rule.match_type {
+            MatchType::Regex => Some(Regex::new(&format!("(?i){}", rule.pattern))?),
+            _ => None,
+        };
+        let pattern_lower = rule.pattern.to_lowercase();
+        Ok(CompiledRule {
+            rule,
+            regex,
+            pattern_lower,
+        })
+    }
+
+    /// `content_lower` must already be lowercased.
+    fn matches(&self, content_lower: &str) -> bool {
+        match self.rule.match_type {
+            MatchType::Substring => content_lower.contains(&self.pattern_lower),
+            MatchType::WholeWord => words(content_lower).contains(&self.pattern_lower.as_str()),
+            MatchType::Phrase => {
+                let phrase = words(&self.pattern_lower);
+                !phrase.is_empty() && words(content_lower).windows(phrase.len()).any(|w| w == phrase)
+            }
+            MatchType::Regex => self.regex.as_ref().is_some_and(|re| re.is_match(content_lower)),
+        }
+    }
+}
+
+/// What a fired rule resolved to, after any random choices were made. Emoji are resolved
+/// against the guild when the action is carried out.
+#[derive(Debug, Clone)]
+pub enum PlannedAction {
+    React(Vec<EmojiSpec>),
+    Reply(String),
+}
+
+fn plan_action<R: Rng>(action: &TriggerAction, rng: &mut R) -> Option<PlannedAction> {
+    match action {
+        TriggerAction::Sequence { emojis } => Some(PlannedAction::React(emojis.clone())),
+        TriggerAction::RandomPick { options, min, max } => {
+            if options.is_empty() {
+                return None;
+            }
+            let count = rng.gen_range(*min.min(max)..=*max).min(options.len());
+            let picked: Vec<EmojiSpec> = options.choose_multiple(rng, count).flatten().cloned().collect();
+            Some(PlannedAction::React(picked))
+        }
+        TriggerAction::WeightedPick { options } => {
+            let dist = WeightedIndex::new(options.iter().map(|o| o.weight)).ok()?;
+            let option = &options[dist.sample(rng)];
+            (!option.emojis.is_empty()).then(|| PlannedAction::React(option.emojis.clone()))
+        }
+        TriggerAction::Reply { text } => Some(PlannedAction::Reply(text.clone())),
+        TriggerAction::Policy(policy) => {
+            let emojis = policy.choose(rng);
+            (!emojis.is_empty()).then_some(PlannedAction::React(emojis))
+        }
+        TriggerAction::Spell { word } => match speller::spell(word) {
+            Ok(sequence) => Some(PlannedAction::React(sequence.iter().map(|e| EmojiSpec::unicode(e)).collect())),
+            Err(e) => {
+                eprintln!("[triggers] Can't spell '{}': {}", word, e);
+                None
+            }
+        },
+    }
+}
+
+/// The live rule set.
+#[derive(Debug, Clone, Default)]
+pub struct TriggerEngine {
+    rules: Arc<RwLock<Vec<CompiledRule>>>,
+}
+
+impl TriggerEngine {
+    fn replace(&self, rules: Vec<TriggerRule>) -> usize {
+        let mut compiled: Vec<CompiledRule> = rules
+            .into_iter()
+            .filter_map(|rule| {
+                let name = rule.name.clone();
+                match CompiledRule::compile(rule) {
+                    Ok(compiled) => Some(compiled),
+                    Err(e) => {
+                        eprintln!("[triggers] Skipping rule '{}': {}", name, e);
+                        None
+                    }
+                }
+            })
+            .collect();
+        compiled.sort_by(|a, b| b.rule.priority.cmp(&a.rule.priority).then(a.rule.id.cmp(&b.rule.id)));
+        let count = compiled.len();
+        *self.rules.write().unwrap() = compiled;
+        count
+    }
+
+    /// Every rule that applies in `guild_id`, highest priority first.
+    pub fn rules_for(&self, guild_id: Option<GuildId>) -> Vec<TriggerRule> {
+        self.rules
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|compiled| compiled.rule.guild_id.is_none() || compiled.rule.guild_id == guild_id)
+            .map(|compiled| compiled.rule.clone())
+            .collect()
+    }
+
+    /// Rules that fire for this message, highest priority first, honouring stop flags.
+    pub fn matching(&self, content: &str, guild_id: Option<GuildId>) -> Vec<TriggerRule> {
+        let content_lower = content.to_lowercase();
+        let rules = self.rules.read().unwrap();
+        let mut fired = Vec::new();
+        for compiled in rules.iter() {
+            let in_scope = compiled.rule.guild_id.is_none() || compiled.rule.guild_id == guild_id;
+            if !in_scope || !compiled.matches(&content_lower) {
+                continue;
+            }
+            fired.push(compiled.rule.clone());
+            if compiled.rule.stop_processing {
+                break;
+            }
+        }
+        fired
+    }
+
+    /// Resolve the fired rules' actions into concrete reactions and replies, each tagged
+    /// with the name of the rule it came from.
+    pub fn plan<R: Rng>(rules: &[TriggerRule], rng: &mut R) -> Vec<(String, PlannedAction)> {
+        rules
+            .iter()
+            .flat_map(|rule| rule.actions.iter().map(move |action| (rule, action)))
+            .filter_map(|(rule, action)| Some((rule.name.clone(), plan_action(action, rng)?)))
+            .collect()
+    }
+
+    /// Re-read the rules table now.
+    pub async fn reload(&self, pool: &PgPool) -> anyhow::Result<usize> {
+        let rules = fetch_rules(pool).await?;
+        Ok(self.replace(rules))
+    }
+}
+
+/// Custom emoji compare by id alone; names can change.
+fn same_reaction(a: &ReactionType, b: &ReactionType) -> bool {
+    match (a, b) {
+        (ReactionType::Custom { id: a, .. }, ReactionType::Custom { id: b, .. }) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Carry out planned actions on a message. A failed reaction stops that action's sequence,
+/// since Discord will refuse the rest for the same reason. Returns each reaction added,
+/// with the rule that added it.
+pub async fn execute(
+    ctx: &Context,
+    msg: &Message,
+    emojis: &EmojiRegistry,
+    planned: Vec<(String, PlannedAction)>,
+) -> Vec<(String, ReactionType)> {
+    let mut added = Vec::new();
+    for (trigger, action) in planned {
+        match action {
+            PlannedAction::React(specs) => {
+                for spec in specs {
+                    let Some(reaction) = emojis.resolve(msg.guild_id, &spec) else {
+                        println!("[triggers] No emoji for {} in this guild, skipping", spec);
+                        continue;
+                    };
+                    // Already there, e.g. from before an edit
+                    if msg.reactions.iter().any(|r| r.me && same_reaction(&r.reaction_type, &reaction)) {
+                        continue;
+                    }
+                    if let Err(why) = msg.react(&ctx.http, reaction.clone()).await {
+                        println!("[triggers] Error adding reaction: {:?}", why);
+                        break;
+                    }
+                    added.push((trigger.clone(), reaction));
+                }
+            }
+            PlannedAction::Reply(text) => {
+                // Rule text is moderator-written; never let it ping anyone
+                let reply = CreateMessage::new().content(text).allowed_mentions(CreateAllowedMentions::new());
+                if let Err(why) = msg.channel_id.send_message(&ctx.http, reply).await {
+                    println!("[triggers] Error sending reply: {:?}", why);
+                }
+            }
+        }
+    }
+    added
+}
+
+pub fn activated_emojis() -> Vec<EmojiSpec> {
+    [emoji_registry::AC, emoji_registry::TI, emoji_registry::VA, emoji_registry::TE, emoji_registry::D]
+        .iter()
+        .map(EmojiRef::spec)
+        .collect()
+}
+
+fn madi_actions() -> Vec<TriggerAction> {
+    vec![TriggerAction::Policy(ReactionPolicy::madi())]
+}
+
+/// The reactions the bot shipped with before rules were configurable.
+fn seed_rules() -> Vec<TriggerRule> {
+    let rule = |name: &str, match_type, pattern: &str, actions, priority| TriggerRule {
+        id: 0,
+        name: name.to_string(),
+        guild_id: None,
+        match_type,
+        pattern: pattern.to_string(),
+        actions,
+        priority,
+        stop_processing: true,
+    };
+    let mut activate = activated_emojis();
+    activate.pop();
+
+    vec![
+        rule(
+            "activated",
+            MatchType::Substring,
+            "activated",
+            vec![TriggerAction::Sequence { emojis: activated_emojis() }],
+            300,
+        ),
+        rule(
+            "activate",
+            MatchType::Substring,
+            "activate",
+            vec![TriggerAction::Sequence { emojis: activate }],
+            200,
+        ),
+        rule("madi parsons", MatchType::Substring, "madi parsons", madi_actions(), 110),
+        rule("madi", MatchType::WholeWord, "madi", madi_actions(), 100),
+    ]
+}
+
+/// Check that a rule's pattern compiles before it's saved.
+pub fn validate(rule: &TriggerRule) -> anyhow::Result<()> {
+    for action in &rule.actions {
+        if let TriggerAction::Policy(policy) = action {
+            policy.validate().map_err(anyhow::Error::msg)?;
+        }
+    }
+    CompiledRule::compile(rule.clone()).map(|_| ())
+}
+
+/// Delete a rule. Global rules (no guild) are only removed when `allow_global` is set.
+/// Returns the removed rule's name.
+pub async fn remove_rule(
+    pool: &PgPool,
+    id: i64,
+    guild_id: GuildId,
+    allow_global: bool,
+) -> anyhow::Result<Option<String>> {
+    let row = sqlx::query_as::<_, (String,)>(
+        "DELETE FROM reaction_triggers
+         WHERE id = $1 AND (guild_id = $2 OR ($3 AND guild_id IS NULL))
+         RETURNING name",
+    )
+    .bind(id)
+    .bind(guild_id.get() as i64)
+    .bind(allow_global)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(|(name,)| name))
+}
+
+pub async fn insert_rule(pool: &PgPool, rule: &TriggerRule) -> anyhow::Result<i64> {
+    let (id,) = sqlx::query_as::<_, (i64,)>(
+        "INSERT INTO reaction_triggers
+            (name, guild_id, match_type, pattern, actions, priority, stop_processing)
+         VALUES ($1, $2, $3, $4, $5::jsonb, $6, $7)
+         RETURNING id",
+    )
+    .bind(&rule.name)
+    .bind(rule.guild_id.map(|g| g.get() as i64))
+    .bind(rule.match_type.as_str())
+    .bind(&rule.pattern)
+    .bind(serde_json::to_string(&rule.actions)?)
+    .bind(rule.priority)
+    .bind(rule.stop_processing)
+    .fetch_one(pool)
+    .await?;
+    Ok(id)
+}
+
+pub async fn ensure_table(pool: &PgPool) -> anyhow::Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS reaction_triggers (
+            id              BIGSERIAL PRIMARY KEY,
+            name            TEXT NOT NULL,
+            guild_id        BIGINT,
+            match_type      TEXT NOT NULL,
+            pattern         TEXT NOT NULL,
+            actions         JSONB NOT NULL,
+            priority        INTEGER NOT NULL DEFAULT 0,
+            stop_processing BOOLEAN NOT NULL DEFAULT FALSE,
+            enabled         BOOLEAN NOT NULL DEFAULT TRUE,
+            created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Seed only an empty table, so removed seed rules stay removed
+    let (count,) = sqlx::query_as::<_, (i64,)>("SELECT COUNT(*) FROM reaction_triggers")
+        .fetch_one(pool)
+        .await?;
+    if count == 0 {
+        for rule in seed_rules() {
+            insert_rule(pool, &rule).await?;
+        }
+        println!("[triggers] Seeded default trigger rules");
+    }
+    Ok(())
+}
+
+type RuleRow = (i64, String, Option<i64>, String, String, String, i32, bool);
+
+async fn fetch_rules(pool: &PgPool) -> anyhow::Result<Vec<TriggerRule>> {
+    let rows = sqlx::query_as::<_, RuleRow>(
+        "SELECT id, name, guild_id, match_type, pattern, actions::text, priority, stop_processing
+         FROM reaction_triggers
+         WHERE enabled",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut rules = Vec::new();
+    for (id, name, guild_id, match_type, pattern, actions, priority, stop_processing) in rows {
+        let Some(match_type) = MatchType::parse(&match_type) else {
+            eprintln!("[triggers] Rule '{}' has unknown match type '{}'", name, match_type);
+            continue;
+        };
+        let actions: Vec<TriggerAction> = match serde_json::from_str(&actions) {
+            Ok(actions) => actions,
+            Err(e) => {
+                eprintln!("[triggers] Rule '{}' has invalid actions: {}", name, e);
+                continue;
+            }
+        };
+        rules.push(TriggerRule {
+            id,
+            name,
+            guild_id: guild_id.map(|g| GuildId::new(g as u64)),
+            match_type,
+            pattern,
+            actions,
+            priority,
+            stop_processing,
+        });
+    }
+    Ok(rules)
+}
+
+/// Load the rules once, then keep re-reading them so edits made straight in the database
+/// take effect without a restart.
+pub async fn run(engine: TriggerEngine, pool: PgPool) {
+    let reload_secs = std::env::var("TRIGGER_RELOAD_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_RELOAD_SECS);
+
+    if let Err(e) = ensure_table(&pool).await {
+        eprintln!("[triggers] Failed to create reaction_triggers table: {}", e);
+    }
+
+    let mut loaded: Option<usize> = None;
+    loop {
+        match engine.reload(&pool).await {
+            Ok(count) => {
+                if loaded != Some(count) {
+                    println!("[triggers] Loaded {} trigger rule(s)", count);
+                }
+                loaded = Some(count);
+            }
+            Err(e) => {
+                eprintln!("[triggers] Reload error: {}", e);
+                // Never loaded anything: run the built-in rules until the database is back
+                if loaded.is_none() {
+                    let count = engine.replace(seed_rules());
+                    println!("[triggers] Using {} built-in trigger rule(s)", count);
+                    loaded = Some(count);
+                }
+            }
+        }
+        sleep(Duration::from_secs(reload_secs)).await;
+    }
+}
@@ -0,0 +1,175 @@
+use sqlx::PgPool;
+use std::collections::HashMap;
+use url::Url;
+
+/// Domain key for parameters stripped from every link.
+const ALL_DOMAINS: &str = "*";
+
+/// Tracking parameters stripped before the bot reposts a link. A trailing `*` matches a
+/// prefix, so `utm_*` covers `utm_source`, `utm_medium` and friends.
+fn default_params() -> Vec<(&'static str, &'static str)> {
+    vec![
+        (ALL_DOMAINS, "utm_*"),
+        (ALL_DOMAINS, "fbclid"),
+        (ALL_DOMAINS, "gclid"),
+        (ALL_DOMAINS, "igshid"),
+        (ALL_DOMAINS, "mc_cid"),
+        (ALL_DOMAINS, "mc_eid"),
+        ("x.com", "s"),
+        ("x.com", "t"),
+        ("x.com", "ref_src"),
+        ("x.com", "ref_url"),
+        ("twitter.com", "s"),
+        ("twitter.com", "t"),
+        ("twitter.com", "ref_src"),
+        ("twitter.com", "ref_url"),
+        ("instagram.com", "igsh"),
+        ("instagram.com", "img_index"),
+        ("youtube.com", "si"),
+        ("youtube.com", "feature"),
+        ("youtu.be", "si"),
+        ("open.spotify.com", "si"),
+        ("tiktok.com", "_r"),
+        ("tiktok.com", "_t"),
+        ("tiktok.com", "is_from_webapp"),
+        ("tiktok.com", "sender_device"),
+        ("reddit.com", "share_id"),
+        ("reddit.com", "rdt"),
+        ("facebook.com", "mibextid"),
+        ("facebook.com", "rdid"),
+        ("threads.net", "xmt"),
+        ("threads.net", "slof"),
+    ]
+}
+
+#[derive(Debug, Clone)]
+pub struct UrlSanitizer {
+    /// Domain (or `*`) → parameter patterns.
+    params: HashMap<String, Vec<String>>,
+}
+
+impl UrlSanitizer {
+    pub fn new<I, D, P>(entries: I) -> Self
+    where
+        I: IntoIterator<Item = (D, P)>,
+        D: Into<String>,
+        P: Into<String>,
+    {
+        let mut params: HashMap<String, Vec<String>> = HashMap::new();
+        for (domain, param) in entries {
+            params
+                .entry(domain.into().to_lowercase())
+                .or_default()
+                .push(param.into());
+        }
+        UrlSanitizer { params }
+    }
+
+    fn is_tracking(&self, host: &str, key: &str) -> bool {
+        self.params
+            .iter()
+            .filter(|(domain, _)| {
+                domain.as_str() == ALL_DOMAINS
+                    || host == domain.as_str()
+                    || host.ends_with(&format!(".{}", domain))
+            })
+            .flat_map(|(_, patterns)| patterns)
+            .any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => key.starts_with(prefix),
+                None => key == pattern,
+            })
+    }
+
+    /// Strip tracking parameters from `url`. Returns None when the URL can't be parsed or
+    /// carried nothing to strip.
+    pub fn clean(&self, url: &str) -> Option<String> {
+        let mut parsed = Url::parse(url).ok()?;
+        let host = parsed.host_str()?.to_lowercase();
+        let query = parsed.query()?.to_string();
+
+        // Filter the raw pairs so kept parameters keep their original encoding
+        let mut removed = false;
+        let kept: Vec<&str> = query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .filter(|pair| {
+                let raw_key = pair.split('=').next().unwrap_or_default();
+                let key: String = url::form_urlencoded::parse(raw_key.as_bytes())
+                    .map(|(k, _)| k.into_owned())
+                    .next()
+                    .unwrap_or_default();
+                let tracking = self.is_tracking(&host, &key);
+                removed |= tracking;
+                !tracking
+            })
+            .collect();
+
+        if !removed {
+            return None;
+        }
+        if kept.is_empty() {
+            parsed.set_query(None);
+        } else {
+            parsed.set_query(Some(&kept.join("&")));
+        }
+        Some(parsed.to_string())
+    }
+}
+
+pub async fn ensure_table(pool: &PgPool) -> anyhow::Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS link_tracking_params (
+            domain TEXT NOT NULL,
+            param  TEXT NOT NULL,
+            PRIMARY KEY (domain, param)
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Seed only an empty table, so removed default parameters stay removed
+    let (count,) = sqlx::query_as::<_, (i64,)>("SELECT COUNT(*) FROM link_tracking_params")
+        .fetch_one(pool)
+        .await?;
+    if count == 0 {
+        for (domain, param) in default_params() {
+            sqlx::query(
+                "INSERT INTO link_tracking_params (domain, param) VALUES ($1, $2)
+                 ON CONFLICT (domain, param) DO NOTHING",
+            )
+            .bind(domain)
+            .bind(param)
+            .execute(pool)
+            .await?;
+        }
+        println!("[url_sanitizer] Seeded default tracking parameters");
+    }
+    Ok(())
+}
+
+/// Load the parameter list, falling back to the built-in list if the database is unavailable.
+pub async fn load(pool: &PgPool) -> UrlSanitizer {
+    let loaded = async {
+        ensure_table(pool).await?;
+        let rows = sqlx::query_as::<_, (String, String)>(
+            "SELECT domain, param FROM link_tracking_params",
+        )
+        .fetch_all(pool)
+        .await?;
+        anyhow::Ok(rows)
+    }
+    .await;
+
+    match loaded {
+        Ok(rows) => {
+            println!("[url_sanitizer] Loaded {} tracking parameter(s)", rows.len());
+            UrlSanitizer::new(rows)
+        }
+        Err(e) => {
+            eprintln!("[url_sanitizer] Failed to load parameters, using defaults: {}", e);
+            UrlSanitizer::new(default_params())
+        }
+    }
+}